@@ -0,0 +1,65 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+import { test } from "node:test";
+import assert from "node:assert/strict";
+import {
+  decodeCipherText,
+  decryptImage,
+  encodeCipherText,
+  encryptImage,
+} from "./ciphertext";
+import fixtures from "./testdata/ciphertexts.json";
+
+// Ciphertexts in the format of `enft-cli --encoding raw encrypt`, which the Rust
+// tests read as well (see `test_typescript_fixtures` in cli/src/ciphertext.rs).
+const hex = (s: string) => Uint8Array.from(Buffer.from(s, "hex"));
+
+for (const fixture of fixtures) {
+  test(`decrypt the ${fixture.name} ciphertext of the CLI`, () => {
+    const ciphertext = hex(fixture.ciphertext);
+    const decoded = decodeCipherText(ciphertext);
+    assert.equal(decoded.header.width, fixture.width);
+    assert.equal(decoded.header.height, fixture.height);
+    assert.equal(decoded.header.region.kind, fixture.name);
+    assert.deepEqual(encodeCipherText(decoded), ciphertext);
+
+    const recovered = decryptImage(
+      hex(fixture.obfuscated_image),
+      ciphertext,
+      hex(fixture.master_key)
+    );
+    assert.deepEqual(recovered, hex(fixture.image));
+
+    // The same image, region and IV give the same ciphertext as the CLI.
+    const encrypted = encryptImage(
+      fixture.width,
+      fixture.height,
+      hex(fixture.image),
+      decoded.header.region,
+      hex(fixture.master_key),
+      decoded.iv
+    );
+    assert.deepEqual(encrypted.obfuscatedRgba, hex(fixture.obfuscated_image));
+    assert.deepEqual(encrypted.ciphertext, ciphertext);
+  });
+}
+
+test("reject a ciphertext for another image or with a tampered header", () => {
+  const fixture = fixtures[0];
+  const obfuscated = hex(fixture.obfuscated_image);
+  const masterKey = hex(fixture.master_key);
+  const ciphertext = hex(fixture.ciphertext);
+
+  const otherImage = Uint8Array.from(obfuscated);
+  otherImage[0] ^= 1;
+  assert.throws(() => decryptImage(otherImage, ciphertext, masterKey));
+
+  const decoded = decodeCipherText(ciphertext);
+  if (decoded.header.region.kind === "chunks") {
+    decoded.header.region.strategy = "count:3";
+  }
+  const tampered = encodeCipherText(decoded);
+  assert.throws(() => decryptImage(obfuscated, tampered, masterKey));
+  assert.throws(() => decodeCipherText(ciphertext.subarray(0, 40)));
+});
@@ -0,0 +1,483 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// The versioned `ENFT` ciphertext container of the Rust CLI (see cli/src/ciphertext.rs),
+// so that the backend and the CLI can read each other's ciphertexts. The container is
+// the BCS encoding of the header, the 12 byte IV and the AES-256-GCM ciphertext of the
+// selected RGBA values, with the BCS encoded header as associated data.
+
+import * as crypto from "crypto";
+import { blake2b } from "@noble/hashes/blake2b";
+import {
+  AES_KEY_LABEL,
+  aesGcmDecrypt,
+  aesGcmEncrypt,
+  deriveAesKey,
+} from "./obfuscate";
+
+export const MAGIC = Uint8Array.from([0x45, 0x4e, 0x46, 0x54]); // "ENFT"
+export const FORMAT_VERSION = 1;
+
+export type CipherSuite =
+  // The key schedule of ciphertexts older than AES_KEY_LABEL. It depends on the
+  // algorithm of Rust's StdRng, so these can only be decrypted by the CLI.
+  | { kind: "aes256GcmLegacyKey" }
+  | { kind: "aes256GcmHkdfSha256"; label: string };
+
+export interface Rectangle {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+export type Region =
+  // Chunks as [row, column] indices, chosen by the described sampling strategy.
+  | {
+      kind: "chunks";
+      geometry: { width: number; height: number };
+      indices: [number, number][];
+      strategy: string;
+    }
+  // Runs of [first pixel index, length] in row-major order.
+  | { kind: "mask"; runs: [number, number][] }
+  // Rectangles and polygons of [x, y] vertices in pixel coordinates.
+  | { kind: "shapes"; rectangles: Rectangle[]; polygons: [number, number][][] };
+
+export interface CipherTextHeader {
+  version: number;
+  cipherSuite: CipherSuite;
+  width: number;
+  height: number;
+  region: Region;
+  // Blake2b256 of the obfuscated image bytes, see `imageBytes`.
+  obfuscatedImageHash: Uint8Array;
+}
+
+export interface CipherTextV1 {
+  header: CipherTextHeader;
+  iv: Uint8Array;
+  data: Uint8Array;
+}
+
+class BcsWriter {
+  private bytes: number[] = [];
+
+  u8(value: number) {
+    this.bytes.push(value & 0xff);
+  }
+
+  u32(value: number) {
+    for (let i = 0; i < 4; i++) {
+      this.u8(Math.floor(value / 2 ** (8 * i)));
+    }
+  }
+
+  u64(value: number) {
+    if (!Number.isSafeInteger(value) || value < 0) {
+      throw new Error(`Invalid u64: ${value}`);
+    }
+    this.u32(value % 2 ** 32);
+    this.u32(Math.floor(value / 2 ** 32));
+  }
+
+  uleb128(value: number) {
+    while (value >= 0x80) {
+      this.u8((value % 0x80) | 0x80);
+      value = Math.floor(value / 0x80);
+    }
+    this.u8(value);
+  }
+
+  fixedBytes(bytes: Uint8Array) {
+    bytes.forEach((b) => this.u8(b));
+  }
+
+  vector<T>(items: T[], write: (item: T) => void) {
+    this.uleb128(items.length);
+    items.forEach(write);
+  }
+
+  byteVector(bytes: Uint8Array) {
+    this.uleb128(bytes.length);
+    this.fixedBytes(bytes);
+  }
+
+  string(value: string) {
+    this.byteVector(new TextEncoder().encode(value));
+  }
+
+  toBytes() {
+    return Uint8Array.from(this.bytes);
+  }
+}
+
+class BcsReader {
+  private offset = 0;
+
+  constructor(private bytes: Uint8Array) {}
+
+  u8() {
+    if (this.offset >= this.bytes.length) {
+      throw new Error("Unexpected end of ciphertext");
+    }
+    return this.bytes[this.offset++];
+  }
+
+  u32() {
+    let value = 0;
+    for (let i = 0; i < 4; i++) {
+      value += this.u8() * 2 ** (8 * i);
+    }
+    return value;
+  }
+
+  u64() {
+    const low = this.u32();
+    const high = this.u32();
+    if (high >= 2 ** 21) {
+      throw new Error("u64 value is too large");
+    }
+    return high * 2 ** 32 + low;
+  }
+
+  uleb128() {
+    let value = 0;
+    for (let shift = 0; shift < 32; shift += 7) {
+      const byte = this.u8();
+      value += (byte & 0x7f) * 2 ** shift;
+      if ((byte & 0x80) === 0) {
+        return value;
+      }
+    }
+    throw new Error("Invalid ULEB128 length");
+  }
+
+  fixedBytes(length: number) {
+    if (this.offset + length > this.bytes.length) {
+      throw new Error("Unexpected end of ciphertext");
+    }
+    const bytes = this.bytes.slice(this.offset, this.offset + length);
+    this.offset += length;
+    return bytes;
+  }
+
+  vector<T>(read: () => T) {
+    const length = this.uleb128();
+    const items: T[] = [];
+    for (let i = 0; i < length; i++) {
+      items.push(read());
+    }
+    return items;
+  }
+
+  byteVector() {
+    return this.fixedBytes(this.uleb128());
+  }
+
+  string() {
+    return new TextDecoder("utf-8", { fatal: true }).decode(this.byteVector());
+  }
+
+  finish() {
+    if (this.offset !== this.bytes.length) {
+      throw new Error("Trailing bytes after the ciphertext");
+    }
+  }
+}
+
+const writeHeader = (writer: BcsWriter, header: CipherTextHeader) => {
+  writer.fixedBytes(MAGIC);
+  writer.u8(header.version);
+  const suite = header.cipherSuite;
+  if (suite.kind === "aes256GcmLegacyKey") {
+    writer.uleb128(0);
+  } else {
+    writer.uleb128(1);
+    writer.string(suite.label);
+  }
+  writer.u32(header.width);
+  writer.u32(header.height);
+  const region = header.region;
+  if (region.kind === "chunks") {
+    writer.uleb128(0);
+    writer.u32(region.geometry.width);
+    writer.u32(region.geometry.height);
+    writer.vector(region.indices, ([i, j]) => {
+      writer.u64(i);
+      writer.u64(j);
+    });
+    writer.string(region.strategy);
+  } else if (region.kind === "mask") {
+    writer.uleb128(1);
+    writer.vector(region.runs, ([start, length]) => {
+      writer.u64(start);
+      writer.u64(length);
+    });
+  } else {
+    writer.uleb128(2);
+    writer.vector(region.rectangles, (rect) => {
+      writer.u32(rect.x);
+      writer.u32(rect.y);
+      writer.u32(rect.width);
+      writer.u32(rect.height);
+    });
+    writer.vector(region.polygons, (polygon) =>
+      writer.vector(polygon, ([x, y]) => {
+        writer.u32(x);
+        writer.u32(y);
+      })
+    );
+  }
+  writer.fixedBytes(header.obfuscatedImageHash);
+};
+
+const readHeader = (reader: BcsReader): CipherTextHeader => {
+  const magic = reader.fixedBytes(MAGIC.length);
+  if (!magic.every((b, i) => b === MAGIC[i])) {
+    throw new Error("Not a versioned ciphertext");
+  }
+  const version = reader.u8();
+  if (version !== FORMAT_VERSION) {
+    throw new Error(`Unsupported ciphertext version ${version}`);
+  }
+  let cipherSuite: CipherSuite;
+  const suite = reader.uleb128();
+  if (suite === 0) {
+    cipherSuite = { kind: "aes256GcmLegacyKey" };
+  } else if (suite === 1) {
+    cipherSuite = { kind: "aes256GcmHkdfSha256", label: reader.string() };
+  } else {
+    throw new Error(`Unknown cipher suite ${suite}`);
+  }
+  const width = reader.u32();
+  const height = reader.u32();
+  const u64Pair = (): [number, number] => [reader.u64(), reader.u64()];
+  const u32Pair = (): [number, number] => [reader.u32(), reader.u32()];
+  let region: Region;
+  const kind = reader.uleb128();
+  if (kind === 0) {
+    region = {
+      kind: "chunks",
+      geometry: { width: reader.u32(), height: reader.u32() },
+      indices: reader.vector(u64Pair),
+      strategy: reader.string(),
+    };
+  } else if (kind === 1) {
+    region = {
+      kind: "mask",
+      runs: reader.vector(u64Pair),
+    };
+  } else if (kind === 2) {
+    region = {
+      kind: "shapes",
+      rectangles: reader.vector(() => ({
+        x: reader.u32(),
+        y: reader.u32(),
+        width: reader.u32(),
+        height: reader.u32(),
+      })),
+      polygons: reader.vector(() => reader.vector(u32Pair)),
+    };
+  } else {
+    throw new Error(`Unknown region ${kind}`);
+  }
+  const obfuscatedImageHash = reader.fixedBytes(32);
+  return { version, cipherSuite, width, height, region, obfuscatedImageHash };
+};
+
+// The BCS encoded header, which is the associated data of the AES-GCM encryption.
+export const encodeHeader = (header: CipherTextHeader) => {
+  const writer = new BcsWriter();
+  writeHeader(writer, header);
+  return writer.toBytes();
+};
+
+export const encodeCipherText = (ciphertext: CipherTextV1) => {
+  const writer = new BcsWriter();
+  writeHeader(writer, ciphertext.header);
+  writer.fixedBytes(ciphertext.iv);
+  writer.byteVector(ciphertext.data);
+  return writer.toBytes();
+};
+
+// Read a v1 ciphertext, as written by `enft-cli --encoding raw encrypt`. The
+// unversioned v0 layout is not supported.
+export const decodeCipherText = (bytes: Uint8Array): CipherTextV1 => {
+  const reader = new BcsReader(bytes);
+  const header = readHeader(reader);
+  const iv = reader.fixedBytes(12);
+  const data = reader.byteVector();
+  reader.finish();
+  return { header, iv, data };
+};
+
+// The image bytes hashed into the header: the width and height as big-endian u32s
+// followed by the RGBA pixels, as `load_image` in the Rust CLI.
+export const imageBytes = (width: number, height: number, rgba: Uint8Array) => {
+  const bytes = new Uint8Array(8 + rgba.length);
+  const view = new DataView(bytes.buffer);
+  view.setUint32(0, width);
+  view.setUint32(4, height);
+  bytes.set(rgba, 8);
+  return bytes;
+};
+
+// Whether the center of pixel (x, y) lies inside the polygon (even-odd rule), with
+// doubled coordinates as in the Rust CLI.
+const polygonCovers = (polygon: [number, number][], x: number, y: number) => {
+  const [px, py] = [2 * x + 1, 2 * y + 1];
+  let inside = false;
+  polygon.forEach(([x1, y1], k) => {
+    const [x2, y2] = polygon[(k + 1) % polygon.length];
+    const [xi, yi, xj, yj] = [2 * x1, 2 * y1, 2 * x2, 2 * y2];
+    if (yi > py !== yj > py) {
+      const lhs = (px - xi) * (yj - yi);
+      const rhs = (xj - xi) * (py - yi);
+      if ((yj > yi && lhs < rhs) || (yj < yi && lhs > rhs)) {
+        inside = !inside;
+      }
+    }
+  });
+  return inside;
+};
+
+// The indices of the selected pixels in the order their values are encrypted, as
+// `Region::pixel_indices` in the Rust CLI.
+export const pixelIndices = (
+  region: Region,
+  width: number,
+  height: number
+): number[] => {
+  const pixels: number[] = [];
+  if (region.kind === "chunks") {
+    const { width: chunkWidth, height: chunkHeight } = region.geometry;
+    if (chunkWidth === 0 || chunkHeight === 0) {
+      throw new Error("Chunk dimensions must be positive");
+    }
+    for (const [i, j] of region.indices) {
+      if (i * chunkHeight >= height || j * chunkWidth >= width) {
+        throw new Error("The region does not match the image");
+      }
+      const rowEnd = Math.min((i + 1) * chunkHeight, height);
+      const columnEnd = Math.min((j + 1) * chunkWidth, width);
+      for (let y = i * chunkHeight; y < rowEnd; y++) {
+        for (let x = j * chunkWidth; x < columnEnd; x++) {
+          pixels.push(y * width + x);
+        }
+      }
+    }
+  } else if (region.kind === "mask") {
+    for (const [start, length] of region.runs) {
+      if (start + length > width * height) {
+        throw new Error("The region does not match the image");
+      }
+      for (let idx = start; idx < start + length; idx++) {
+        pixels.push(idx);
+      }
+    }
+  } else {
+    if (region.polygons.some((polygon) => polygon.length < 3)) {
+      throw new Error("A polygon needs at least 3 vertices");
+    }
+    for (let idx = 0; idx < width * height; idx++) {
+      const [x, y] = [idx % width, Math.floor(idx / width)];
+      if (
+        region.rectangles.some(
+          (r) =>
+            r.x <= x && x < r.x + r.width && r.y <= y && y < r.y + r.height
+        ) ||
+        region.polygons.some((polygon) => polygonCovers(polygon, x, y))
+      ) {
+        pixels.push(idx);
+      }
+    }
+  }
+  return pixels;
+};
+
+const aesKey = (cipherSuite: CipherSuite, masterKey: Uint8Array) => {
+  if (cipherSuite.kind === "aes256GcmLegacyKey") {
+    throw new Error("Ciphertexts with the legacy key schedule need the CLI");
+  }
+  return deriveAesKey(masterKey, cipherSuite.label);
+};
+
+// Encrypt the selected pixels of an RGBA image under the (compressed, 48 byte)
+// master key, and make them transparent in the obfuscated image, as
+// `enft-cli --encoding raw encrypt` with the default style.
+export const encryptImage = (
+  width: number,
+  height: number,
+  rgba: Uint8Array,
+  region: Region,
+  masterKey: Uint8Array,
+  iv: Uint8Array = crypto.randomBytes(12)
+) => {
+  if (rgba.length !== 4 * width * height) {
+    throw new Error("The pixels do not match the image dimensions");
+  }
+  const indices = pixelIndices(region, width, height);
+  const selectedValues = new Uint8Array(4 * indices.length);
+  const obfuscatedRgba = Uint8Array.from(rgba);
+  indices.forEach((idx, k) => {
+    selectedValues.set(rgba.subarray(4 * idx, 4 * idx + 4), 4 * k);
+    obfuscatedRgba.fill(0, 4 * idx, 4 * idx + 4);
+  });
+
+  const header: CipherTextHeader = {
+    version: FORMAT_VERSION,
+    cipherSuite: { kind: "aes256GcmHkdfSha256", label: AES_KEY_LABEL },
+    width,
+    height,
+    region,
+    obfuscatedImageHash: blake2b(imageBytes(width, height, obfuscatedRgba), {
+      dkLen: 32,
+    }),
+  };
+  const data = aesGcmEncrypt(
+    aesKey(header.cipherSuite, masterKey),
+    iv,
+    encodeHeader(header),
+    selectedValues
+  );
+  return {
+    obfuscatedRgba,
+    ciphertext: encodeCipherText({ header, iv, data: Uint8Array.from(data) }),
+  };
+};
+
+// Recover the RGBA pixels of the original image from the obfuscated image and a v1
+// ciphertext, checking that the ciphertext belongs to the image and is intact.
+export const decryptImage = (
+  obfuscatedRgba: Uint8Array,
+  ciphertext: Uint8Array,
+  masterKey: Uint8Array
+) => {
+  const { header, iv, data } = decodeCipherText(ciphertext);
+  const { width, height } = header;
+  const hash = blake2b(imageBytes(width, height, obfuscatedRgba), {
+    dkLen: 32,
+  });
+  if (
+    obfuscatedRgba.length !== 4 * width * height ||
+    !hash.every((b, i) => b === header.obfuscatedImageHash[i])
+  ) {
+    throw new Error("The ciphertext does not belong to the image");
+  }
+  const selectedValues = aesGcmDecrypt(
+    aesKey(header.cipherSuite, masterKey),
+    iv,
+    encodeHeader(header),
+    data
+  );
+  const indices = pixelIndices(header.region, width, height);
+  if (selectedValues.length !== 4 * indices.length) {
+    throw new Error("The ciphertext does not match its region");
+  }
+  const rgba = Uint8Array.from(obfuscatedRgba);
+  indices.forEach((idx, k) => {
+    rgba.set(selectedValues.subarray(4 * k, 4 * k + 4), 4 * idx);
+  });
+  return rgba;
+};
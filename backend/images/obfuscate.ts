@@ -7,6 +7,7 @@ import { PrivateKey } from "eciesjs";
 import { bls12_381 } from "@noble/curves/bls12-381";
 import { ProjPointType } from "@noble/curves/abstract/weierstrass";
 import { sha256 } from "@noble/hashes/sha256";
+import { hkdf } from "@noble/hashes/hkdf";
 
 interface ColorValues {
   [key: string]: number[];
@@ -181,6 +182,48 @@ const aesDecrypt = async (data: string, secretKey: Uint8Array) => {
   decrypted += decipher.final("utf-8");
   return decrypted;
 };
+// The label of the AES key derived from a master key, recorded in the ciphertexts
+// of the Rust CLI.
+export const AES_KEY_LABEL = "enft/v1/aes-key";
+
+// Derive the AES key of a (compressed, 48 byte) master key with HKDF-SHA256 and no
+// salt, as `derive_aes_key` in the Rust CLI. For the G1 generator this is
+// 6b1bf91413e04c69c5010f23c85ef05ccda990dd099af0432f04d52eba625f4b.
+export const deriveAesKey = (
+  masterKey: Uint8Array,
+  label: string = AES_KEY_LABEL
+) => {
+  return hkdf(sha256, masterKey, undefined, label, 32);
+};
+
+// AES-256-GCM with a 12 byte IV. The output is the ciphertext followed by the
+// 16 byte tag, the same layout as the Rust CLI.
+export const aesGcmEncrypt = (
+  key: Uint8Array,
+  iv: Uint8Array,
+  aad: Uint8Array,
+  data: Uint8Array
+) => {
+  const cipher = crypto.createCipheriv("aes-256-gcm", key, iv);
+  cipher.setAAD(aad);
+  return Buffer.concat([cipher.update(data), cipher.final(), cipher.getAuthTag()]);
+};
+
+export const aesGcmDecrypt = (
+  key: Uint8Array,
+  iv: Uint8Array,
+  aad: Uint8Array,
+  data: Uint8Array
+) => {
+  const decipher = crypto.createDecipheriv("aes-256-gcm", key, iv);
+  decipher.setAAD(aad);
+  decipher.setAuthTag(data.subarray(data.length - 16));
+  return Buffer.concat([
+    decipher.update(data.subarray(0, data.length - 16)),
+    decipher.final(),
+  ]);
+};
+
 // method can be "edgeDetection" or "uniform" or "cross"
 export const obfuscate = async (image: string, method: string = "cross") => {
   let selectedPixels: number[][] = [];
@@ -0,0 +1,145 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::{EnftError, EnftResult};
+use crate::utils::g1_element_from_bytes;
+use fastcrypto::encoding::{Base64, Encoding, Hex};
+use fastcrypto::groups::bls12381::G1Element;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+
+/// How binary blobs such as encrypted master keys, proofs and ciphertexts are
+/// encoded in arguments, files and output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlobEncoding {
+    #[default]
+    Hex,
+    Base64,
+    /// The bytes themselves, e.g. raw BCS. Blobs given inline on the command line and
+    /// printed blobs cannot be raw, so they are hex encoded instead.
+    Raw,
+}
+
+impl BlobEncoding {
+    /// Encode a blob for a file.
+    pub fn encode(&self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            BlobEncoding::Raw => bytes.to_vec(),
+            _ => self.encode_text(bytes).into_bytes(),
+        }
+    }
+
+    /// Encode a blob as text for printing.
+    pub fn encode_text(&self, bytes: &[u8]) -> String {
+        match self {
+            BlobEncoding::Base64 => Base64::encode(bytes),
+            BlobEncoding::Hex | BlobEncoding::Raw => Hex::encode(bytes),
+        }
+    }
+
+    /// Decode the contents of a file. Surrounding whitespace is ignored unless raw.
+    pub fn decode(&self, data: &[u8]) -> EnftResult<Vec<u8>> {
+        match self {
+            BlobEncoding::Raw => Ok(data.to_vec()),
+            _ => {
+                let text = std::str::from_utf8(data).map_err(|_| self.invalid())?;
+                self.decode_text(text)
+            }
+        }
+    }
+
+    /// Decode a blob given as text.
+    pub fn decode_text(&self, text: &str) -> EnftResult<Vec<u8>> {
+        match self {
+            BlobEncoding::Base64 => Base64::decode(text.trim()).map_err(|_| self.invalid()),
+            BlobEncoding::Hex | BlobEncoding::Raw => {
+                Hex::decode(text.trim()).map_err(|_| self.invalid())
+            }
+        }
+    }
+
+    fn invalid(&self) -> EnftError {
+        match self {
+            BlobEncoding::Base64 => EnftError::InvalidBase64,
+            _ => EnftError::InvalidHex,
+        }
+    }
+}
+
+impl fmt::Display for BlobEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlobEncoding::Hex => write!(f, "hex"),
+            BlobEncoding::Base64 => write!(f, "base64"),
+            BlobEncoding::Raw => write!(f, "raw"),
+        }
+    }
+}
+
+impl FromStr for BlobEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hex" => Ok(BlobEncoding::Hex),
+            "base64" => Ok(BlobEncoding::Base64),
+            "raw" => Ok(BlobEncoding::Raw),
+            _ => Err(format!(
+                "Unknown encoding '{}', expected one of hex, base64, raw",
+                s
+            )),
+        }
+    }
+}
+
+/// Read a blob argument: `@path` reads the file, `-` reads stdin, and anything else
+/// is the blob itself as text.
+pub fn read_blob(arg: &str, encoding: BlobEncoding) -> EnftResult<Vec<u8>> {
+    if arg == "-" {
+        let mut data = vec![];
+        std::io::stdin().read_to_end(&mut data)?;
+        encoding.decode(&data)
+    } else if let Some(path) = arg.strip_prefix('@') {
+        encoding.decode(&std::fs::read(path)?)
+    } else {
+        encoding.decode_text(arg)
+    }
+}
+
+/// Read a BCS blob argument, e.g. an ElGamalEncryption or an EqualityProof.
+pub fn read_bcs<T: DeserializeOwned>(arg: &str, encoding: BlobEncoding) -> EnftResult<T> {
+    Ok(bcs::from_bytes(&read_blob(arg, encoding)?)?)
+}
+
+/// Read a G1 element argument, e.g. an encryption pubkey.
+pub fn read_g1_element(arg: &str, encoding: BlobEncoding) -> EnftResult<G1Element> {
+    g1_element_from_bytes(read_blob(arg, encoding)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encodings() {
+        let bytes = vec![0u8, 1, 2, 0xfe, 0xff];
+        for encoding in [BlobEncoding::Hex, BlobEncoding::Base64, BlobEncoding::Raw] {
+            assert_eq!(encoding.decode(&encoding.encode(&bytes)).unwrap(), bytes);
+            assert_eq!(encoding.to_string().parse::<BlobEncoding>(), Ok(encoding));
+        }
+        assert_eq!(BlobEncoding::Base64.encode_text(&bytes), "AAEC/v8=");
+        assert_eq!(BlobEncoding::Raw.encode_text(&bytes), "000102feff");
+
+        let path = std::env::temp_dir().join(format!("enft-blob-{}", rand::random::<u64>()));
+        std::fs::write(&path, "AAEC/v8=\n").unwrap();
+        let arg = format!("@{}", path.display());
+        assert_eq!(read_blob(&arg, BlobEncoding::Base64).unwrap(), bytes);
+        assert!(matches!(
+            read_blob(&arg, BlobEncoding::Hex),
+            Err(EnftError::InvalidHex)
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,324 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::{EnftError, EnftResult};
+use crate::region::{ChunkGeometry, Region};
+use crate::utils::{
+    image_dimensions, msk_to_cipher, msk_to_hkdf_cipher, PreprocessedImage, AES_KEY_LABEL,
+};
+use fastcrypto::aes::{Aes256Gcm, AuthenticatedCipher, Cipher, InitializationVector};
+use fastcrypto::groups::bls12381::G1Element;
+use fastcrypto::hash::{Blake2b256, HashFunction};
+use fastcrypto::traits::{AllowedRng, Generate};
+use serde::{Deserialize, Serialize};
+use typenum::U12;
+
+/// The magic bytes at the start of every versioned ciphertext.
+pub const MAGIC: [u8; 4] = *b"ENFT";
+
+/// The current ciphertext format version. Version 0 is the unversioned layout.
+pub const FORMAT_VERSION: u8 = 1;
+
+/// The strategy recorded for v0 ciphertexts, which did not record one.
+pub const UNRECORDED_STRATEGY: &str = "unrecorded";
+
+/// The algorithms used to encrypt the selected pixel values.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CipherSuite {
+    /// AES-256-GCM with the key derived from the master key by [msk_to_cipher].
+    Aes256GcmLegacyKey,
+    /// AES-256-GCM with the key derived from the master key by
+    /// [crate::utils::derive_aes_key] with the given label.
+    Aes256GcmHkdfSha256 { label: String },
+}
+
+impl CipherSuite {
+    fn cipher(&self, master_key: &G1Element) -> Aes256Gcm<U12> {
+        match self {
+            CipherSuite::Aes256GcmLegacyKey => msk_to_cipher(master_key),
+            CipherSuite::Aes256GcmHkdfSha256 { label } => msk_to_hkdf_cipher(master_key, label),
+        }
+    }
+}
+
+/// The header of a versioned ciphertext. It is bound to the encrypted data as
+/// AES-GCM associated data, so the region cannot be tampered with and the
+/// ciphertext cannot be paired with a different obfuscated image.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherTextHeader {
+    pub magic: [u8; 4],
+    pub version: u8,
+    pub cipher_suite: CipherSuite,
+    pub width: u32,
+    pub height: u32,
+    /// The obfuscated region, including the chunk geometry for sampled chunks.
+    pub region: Region,
+    /// Blake2b256 of the obfuscated image bytes, as returned by [crate::utils::load_image].
+    pub obfuscated_image_hash: [u8; 32],
+}
+
+/// A v1 ciphertext: the authenticated header and the encrypted pixel values.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CipherTextV1 {
+    pub header: CipherTextHeader,
+    pub iv: InitializationVector<U12>,
+    pub data: Vec<u8>,
+}
+
+/// The unversioned ciphertext layout with the indices of 100x100 chunks. Nothing
+/// but the encrypted data is authenticated.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CipherTextV0 {
+    pub pixels: Vec<(usize, usize)>,
+    pub iv: InitializationVector<U12>,
+    pub data: Vec<u8>,
+}
+
+/// A ciphertext of the selected pixel values of an NFT, in any supported format.
+/// New ciphertexts are always created as v1.
+#[derive(Clone)]
+pub enum FullCipherText {
+    V0(CipherTextV0),
+    V1(CipherTextV1),
+}
+
+impl FullCipherText {
+    /// Encrypt the selected values of the preprocessed image under the AES key derived
+    /// from the master key, binding the header with the region and the hash of the
+    /// obfuscated image.
+    pub fn encrypt<R: AllowedRng>(
+        preprocessed: &PreprocessedImage,
+        master_key: &G1Element,
+        rng: &mut R,
+    ) -> EnftResult<Self> {
+        let (width, height) = image_dimensions(&preprocessed.obfuscated_image)?;
+        let header = CipherTextHeader {
+            magic: MAGIC,
+            version: FORMAT_VERSION,
+            cipher_suite: CipherSuite::Aes256GcmHkdfSha256 {
+                label: AES_KEY_LABEL.to_string(),
+            },
+            width,
+            height,
+            region: preprocessed.region.clone(),
+            obfuscated_image_hash: Blake2b256::digest(&preprocessed.obfuscated_image).digest,
+        };
+        let iv = InitializationVector::<U12>::generate(rng);
+        let data = header
+            .cipher_suite
+            .cipher(master_key)
+            .encrypt_authenticated(
+                &iv,
+                &bcs::to_bytes(&header).unwrap(),
+                &preprocessed.selected_values,
+            );
+        Ok(FullCipherText::V1(CipherTextV1 { header, iv, data }))
+    }
+
+    /// Decrypt the selected pixel values. For v1 ciphertexts this also checks that the
+    /// ciphertext belongs to the given obfuscated image and that the header is intact.
+    pub fn decrypt(&self, obfuscated_image: &[u8], master_key: &G1Element) -> EnftResult<Vec<u8>> {
+        match self {
+            FullCipherText::V0(ciphertext) => msk_to_cipher(master_key)
+                .decrypt(&ciphertext.iv, &ciphertext.data)
+                .map_err(|_| EnftError::AuthenticationFailed),
+            FullCipherText::V1(ciphertext) => {
+                let header = &ciphertext.header;
+                if image_dimensions(obfuscated_image)? != (header.width, header.height)
+                    || Blake2b256::digest(obfuscated_image).digest != header.obfuscated_image_hash
+                {
+                    return Err(EnftError::ImageMismatch);
+                }
+                header
+                    .cipher_suite
+                    .cipher(master_key)
+                    .decrypt_authenticated(
+                        &ciphertext.iv,
+                        &bcs::to_bytes(header).unwrap(),
+                        &ciphertext.data,
+                    )
+                    .map_err(|_| EnftError::AuthenticationFailed)
+            }
+        }
+    }
+
+    /// The obfuscated region. For v0 ciphertexts these are 100x100 chunks.
+    pub fn region(&self) -> Region {
+        match self {
+            FullCipherText::V0(ciphertext) => Region::Chunks {
+                geometry: ChunkGeometry::default(),
+                indices: ciphertext.pixels.clone(),
+                strategy: UNRECORDED_STRATEGY.to_string(),
+            },
+            FullCipherText::V1(ciphertext) => ciphertext.header.region.clone(),
+        }
+    }
+
+    pub fn version(&self) -> u8 {
+        match self {
+            FullCipherText::V0(_) => 0,
+            FullCipherText::V1(ciphertext) => ciphertext.header.version,
+        }
+    }
+
+    pub fn iv(&self) -> &InitializationVector<U12> {
+        match self {
+            FullCipherText::V0(ciphertext) => &ciphertext.iv,
+            FullCipherText::V1(ciphertext) => &ciphertext.iv,
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        match self {
+            FullCipherText::V0(ciphertext) => &ciphertext.data,
+            FullCipherText::V1(ciphertext) => &ciphertext.data,
+        }
+    }
+
+    /// Serialize the ciphertext in its own format version.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            FullCipherText::V0(ciphertext) => bcs::to_bytes(ciphertext).unwrap(),
+            FullCipherText::V1(ciphertext) => bcs::to_bytes(ciphertext).unwrap(),
+        }
+    }
+
+    /// Deserialize a ciphertext. Blobs starting with the magic bytes are read as
+    /// versioned ciphertexts, anything else as v0.
+    pub fn from_bytes(bytes: &[u8]) -> EnftResult<Self> {
+        if bytes.starts_with(&MAGIC) && bytes.len() > MAGIC.len() {
+            let version = bytes[MAGIC.len()];
+            let v1 = bcs::from_bytes::<CipherTextV1>(bytes);
+            if let (FORMAT_VERSION, Ok(ciphertext)) = (version, &v1) {
+                return Ok(FullCipherText::V1(ciphertext.clone()));
+            }
+            // A v0 blob may start with the magic bytes by chance.
+            return match bcs::from_bytes(bytes) {
+                Ok(ciphertext) => Ok(FullCipherText::V0(ciphertext)),
+                Err(_) if version != FORMAT_VERSION => Err(EnftError::UnsupportedVersion(version)),
+                Err(e) => Err(v1.err().unwrap_or(e).into()),
+            };
+        }
+        Ok(FullCipherText::V0(bcs::from_bytes(bytes)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::region::{Rectangle, RegionSpec};
+    use crate::style::ObfuscationStyle;
+    use crate::utils::{g1_element_from_bytes, obfuscate_image, recover_image, LoadedImage};
+    use fastcrypto::encoding::{Encoding, Hex};
+    use fastcrypto::groups::bls12381::Scalar;
+    use fastcrypto::groups::{GroupElement, Scalar as _};
+
+    fn test_image(seed: u8) -> LoadedImage {
+        let (width, height) = (20u32, 10u32);
+        LoadedImage {
+            data: [
+                width.to_be_bytes().to_vec(),
+                height.to_be_bytes().to_vec(),
+                vec![seed; (4 * width * height) as usize],
+            ]
+            .concat(),
+            width,
+            height,
+        }
+    }
+
+    fn rectangle(x: u32) -> Region {
+        Region::Shapes(RegionSpec {
+            rectangles: vec![Rectangle {
+                x,
+                y: 2,
+                width: 5,
+                height: 5,
+            }],
+            polygons: vec![],
+        })
+    }
+
+    #[test]
+    fn test_authenticated_header() {
+        let mut rng = rand::thread_rng();
+        let master_key = G1Element::generator() * Scalar::rand(&mut rng);
+        let preprocessed =
+            obfuscate_image(&test_image(7), rectangle(1), &ObfuscationStyle::default()).unwrap();
+        let ciphertext = FullCipherText::encrypt(&preprocessed, &master_key, &mut rng).unwrap();
+
+        let ciphertext = FullCipherText::from_bytes(&ciphertext.to_bytes()).unwrap();
+        assert_eq!(ciphertext.version(), FORMAT_VERSION);
+        assert_eq!(
+            ciphertext
+                .decrypt(&preprocessed.obfuscated_image, &master_key)
+                .unwrap(),
+            preprocessed.selected_values
+        );
+
+        // Paired with a different obfuscated image.
+        let other =
+            obfuscate_image(&test_image(8), rectangle(1), &ObfuscationStyle::default()).unwrap();
+        assert!(matches!(
+            ciphertext.decrypt(&other.obfuscated_image, &master_key),
+            Err(EnftError::ImageMismatch)
+        ));
+
+        // Tampered coordinates.
+        let FullCipherText::V1(mut tampered) = ciphertext else {
+            panic!("expected a v1 ciphertext");
+        };
+        tampered.header.region = rectangle(2);
+        assert!(matches!(
+            FullCipherText::V1(tampered).decrypt(&preprocessed.obfuscated_image, &master_key),
+            Err(EnftError::AuthenticationFailed)
+        ));
+    }
+
+    #[test]
+    fn test_typescript_fixtures() {
+        // Shared with the TypeScript backend, see backend/images/ciphertext.test.ts.
+        let fixtures: Vec<serde_json::Value> = serde_json::from_str(include_str!(
+            "../../backend/images/testdata/ciphertexts.json"
+        ))
+        .unwrap();
+        for fixture in fixtures {
+            let hex = |key: &str| Hex::decode(fixture[key].as_str().unwrap()).unwrap();
+            let dimension = |key: &str| fixture[key].as_u64().unwrap() as u32;
+            let (width, height) = (dimension("width"), dimension("height"));
+            let master_key = g1_element_from_bytes(hex("master_key")).unwrap();
+            let image = LoadedImage::from_rgba(width, height, &hex("image")).unwrap();
+            let obfuscated =
+                LoadedImage::from_rgba(width, height, &hex("obfuscated_image")).unwrap();
+
+            let ciphertext = FullCipherText::from_bytes(&hex("ciphertext")).unwrap();
+            assert_eq!(ciphertext.version(), FORMAT_VERSION);
+            assert_eq!(ciphertext.to_bytes(), hex("ciphertext"));
+            let preprocessed =
+                obfuscate_image(&image, ciphertext.region(), &ObfuscationStyle::default()).unwrap();
+            assert_eq!(preprocessed.obfuscated_image, obfuscated.data);
+            let recovered = recover_image(&obfuscated.data, ciphertext, master_key).unwrap();
+            assert_eq!(recovered, image.data);
+        }
+    }
+
+    #[test]
+    fn test_read_v0() {
+        let mut rng = rand::thread_rng();
+        let master_key = G1Element::generator() * Scalar::rand(&mut rng);
+        let iv = InitializationVector::<U12>::generate(&mut rng);
+        let v0 = CipherTextV0 {
+            pixels: vec![(0, 1)],
+            data: msk_to_cipher(&master_key).encrypt(&iv, &[1, 2, 3, 4]),
+            iv,
+        };
+        let ciphertext = FullCipherText::from_bytes(&bcs::to_bytes(&v0).unwrap()).unwrap();
+        assert_eq!(ciphertext.version(), 0);
+        assert_eq!(
+            ciphertext
+                .decrypt(&test_image(0).data, &master_key)
+                .unwrap(),
+            vec![1, 2, 3, 4]
+        );
+    }
+}
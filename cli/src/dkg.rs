@@ -0,0 +1,377 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A Pedersen (Joint-Feldman) distributed key generation for co-owners of an NFT.
+//!
+//! Each of the n parties deals a random polynomial of degree t - 1: it broadcasts a
+//! [Commitment] to the coefficients and sends each party its [DealtShare] over a
+//! private channel. Every party checks the shares it received against the
+//! commitments and sums them into its share of the joint secret. The joint public
+//! key is the sum of the committed constant terms, so no party ever learns the
+//! joint secret. The joint public key is an ordinary encryption pubkey: it can be
+//! used with `encrypt --enc-pk` and `transfer --buyer-pk` as is, and any t parties
+//! decrypt a master key encrypted under it by combining [DecryptionShare]s.
+
+use crate::elgamal;
+use crate::elgamal::{DleqProof, ElGamalEncryption};
+use crate::error::{EnftError, EnftResult};
+use crate::threshold::{
+    combine_partial_decryptions, evaluate_commitment, evaluate_polynomial, random_polynomial,
+    KeyShare, ThresholdPublicKey,
+};
+use crate::utils::{parse_bcs, parse_g1_element, parse_scalar};
+use fastcrypto::encoding::{Encoding, Hex};
+use fastcrypto::groups::bls12381::{G1Element, Scalar};
+use fastcrypto::groups::GroupElement;
+use fastcrypto::serde_helpers::ToFromByteArray;
+use fastcrypto::traits::AllowedRng;
+use serde::{Deserialize, Serialize};
+
+/// The public commitment `a_k * G` to the coefficients of a dealer's polynomial.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "CommitmentJson", into = "CommitmentJson")]
+pub struct Commitment {
+    pub dealer: u16,
+    pub threshold: u16,
+    pub parties: u16,
+    pub coefficients: Vec<G1Element>,
+}
+
+/// The evaluation of a dealer's polynomial at the index of the recipient. This is a
+/// secret and must only be sent to the recipient.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "DealtShareJson", into = "DealtShareJson")]
+pub struct DealtShare {
+    pub dealer: u16,
+    pub recipient: u16,
+    pub share: Scalar,
+}
+
+/// A partial decryption `ephemeral * share` of an encrypted master key by one party,
+/// with a proof that it was computed with the party's share of the joint key.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "DecryptionShareJson", into = "DecryptionShareJson")]
+pub struct DecryptionShare {
+    pub index: u16,
+    pub partial: G1Element,
+    pub proof: DleqProof,
+}
+
+/// Deal a random polynomial as party `dealer` of `parties`: the commitment to
+/// broadcast and one share per party, including the dealer itself. The polynomial
+/// is not returned.
+pub fn deal<R: AllowedRng>(
+    dealer: u16,
+    threshold: u16,
+    parties: u16,
+    rng: &mut R,
+) -> EnftResult<(Commitment, Vec<DealtShare>)> {
+    if threshold == 0 || threshold > parties {
+        return Err(EnftError::Threshold(format!(
+            "Invalid threshold {} of {} parties",
+            threshold, parties
+        )));
+    }
+    if dealer == 0 || dealer > parties {
+        return Err(EnftError::Threshold(format!(
+            "Invalid party index {} of {} parties",
+            dealer, parties
+        )));
+    }
+    let polynomial = random_polynomial(threshold, rng);
+    let gen = G1Element::generator();
+    let commitment = Commitment {
+        dealer,
+        threshold,
+        parties,
+        coefficients: polynomial.iter().map(|a| gen * *a).collect(),
+    };
+    let shares = (1..=parties)
+        .map(|recipient| DealtShare {
+            dealer,
+            recipient,
+            share: evaluate_polynomial(&polynomial, recipient),
+        })
+        .collect();
+    Ok((commitment, shares))
+}
+
+/// Check a received share against the dealer's commitment.
+pub fn verify_share(commitment: &Commitment, share: &DealtShare) -> EnftResult<()> {
+    if commitment.dealer != share.dealer
+        || G1Element::generator() * share.share
+            != evaluate_commitment(&commitment.coefficients, share.recipient)
+    {
+        return Err(EnftError::Threshold(format!(
+            "Invalid share from party {}",
+            share.dealer
+        )));
+    }
+    Ok(())
+}
+
+/// Compute the share of party `index` of the joint key and the joint public key from
+/// the commitments of the qualified dealers and their shares for this party. All
+/// parties must use the same commitments: a dealer whose share does not verify is
+/// reported by the error, and must be left out by everyone.
+pub fn finalize(
+    index: u16,
+    commitments: &[Commitment],
+    shares: &[DealtShare],
+) -> EnftResult<(KeyShare, ThresholdPublicKey)> {
+    let (threshold, parties) = match commitments.first() {
+        Some(commitment) => (commitment.threshold, commitment.parties),
+        None => return Err(EnftError::Threshold("No commitments given".to_string())),
+    };
+    let mut dealers: Vec<u16> = commitments.iter().map(|c| c.dealer).collect();
+    dealers.sort_unstable();
+    dealers.dedup();
+    if dealers.len() != commitments.len() {
+        return Err(EnftError::Threshold(
+            "Duplicate commitments from a party".to_string(),
+        ));
+    }
+    if let Some(commitment) = commitments.iter().find(|c| {
+        c.threshold != threshold
+            || c.parties != parties
+            || c.coefficients.len() != threshold as usize
+    }) {
+        return Err(EnftError::Threshold(format!(
+            "Commitment of party {} does not match the parameters",
+            commitment.dealer
+        )));
+    }
+
+    let mut joint_share = Scalar::zero();
+    for commitment in commitments {
+        let share = shares
+            .iter()
+            .find(|s| s.dealer == commitment.dealer && s.recipient == index)
+            .ok_or_else(|| {
+                EnftError::Threshold(format!("Missing share from party {}", commitment.dealer))
+            })?;
+        verify_share(commitment, share)?;
+        joint_share = joint_share + share.share;
+    }
+
+    let public_key = ThresholdPublicKey {
+        threshold,
+        master_pk: commitments
+            .iter()
+            .fold(G1Element::zero(), |acc, c| acc + c.coefficients[0]),
+        verification_keys: (1..=parties)
+            .map(|j| {
+                commitments.iter().fold(G1Element::zero(), |acc, c| {
+                    acc + evaluate_commitment(&c.coefficients, j)
+                })
+            })
+            .collect(),
+    };
+    let key_share = KeyShare {
+        index,
+        share: joint_share,
+    };
+    Ok((key_share, public_key))
+}
+
+/// Partially decrypt a master key encrypted under the joint public key.
+pub fn decryption_share<R: AllowedRng>(
+    key_share: &KeyShare,
+    enc: &ElGamalEncryption,
+    rng: &mut R,
+) -> DecryptionShare {
+    let (partial, proof) = elgamal::partial_decrypt(&enc.ephemeral, &key_share.share, rng);
+    DecryptionShare {
+        index: key_share.index,
+        partial,
+        proof,
+    }
+}
+
+/// Check the decryption shares of at least `threshold` parties and recover the master
+/// key encrypted under the joint public key.
+pub fn combine_decryption_shares(
+    public_key: &ThresholdPublicKey,
+    enc: &ElGamalEncryption,
+    shares: &[DecryptionShare],
+) -> EnftResult<G1Element> {
+    for share in shares {
+        elgamal::verify_partial_decryption(
+            &public_key.verification_key(share.index)?,
+            &enc.ephemeral,
+            &share.partial,
+            &share.proof,
+        )?;
+    }
+    let partials: Vec<(u16, G1Element)> = shares.iter().map(|s| (s.index, s.partial)).collect();
+    let partial = combine_partial_decryptions(public_key.threshold, &partials)?;
+    Ok(elgamal::decrypt_with_partial(enc, &partial))
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct CommitmentJson {
+    dealer: u16,
+    threshold: u16,
+    parties: u16,
+    coefficients: Vec<String>,
+}
+
+impl TryFrom<CommitmentJson> for Commitment {
+    type Error = EnftError;
+
+    fn try_from(json: CommitmentJson) -> Result<Self, Self::Error> {
+        Ok(Commitment {
+            dealer: json.dealer,
+            threshold: json.threshold,
+            parties: json.parties,
+            coefficients: json
+                .coefficients
+                .iter()
+                .map(|c| parse_g1_element(c))
+                .collect::<EnftResult<_>>()?,
+        })
+    }
+}
+
+impl From<Commitment> for CommitmentJson {
+    fn from(commitment: Commitment) -> Self {
+        CommitmentJson {
+            dealer: commitment.dealer,
+            threshold: commitment.threshold,
+            parties: commitment.parties,
+            coefficients: commitment
+                .coefficients
+                .iter()
+                .map(|c| Hex::encode(c.to_byte_array()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct DealtShareJson {
+    dealer: u16,
+    recipient: u16,
+    share: String,
+}
+
+impl TryFrom<DealtShareJson> for DealtShare {
+    type Error = EnftError;
+
+    fn try_from(json: DealtShareJson) -> Result<Self, Self::Error> {
+        Ok(DealtShare {
+            dealer: json.dealer,
+            recipient: json.recipient,
+            share: parse_scalar(&json.share)?,
+        })
+    }
+}
+
+impl From<DealtShare> for DealtShareJson {
+    fn from(share: DealtShare) -> Self {
+        DealtShareJson {
+            dealer: share.dealer,
+            recipient: share.recipient,
+            share: Hex::encode(share.share.to_byte_array()),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct DecryptionShareJson {
+    index: u16,
+    partial: String,
+    /// A hex encoded BCS DleqProof.
+    proof: String,
+}
+
+impl TryFrom<DecryptionShareJson> for DecryptionShare {
+    type Error = EnftError;
+
+    fn try_from(json: DecryptionShareJson) -> Result<Self, Self::Error> {
+        Ok(DecryptionShare {
+            index: json.index,
+            partial: parse_g1_element(&json.partial)?,
+            proof: parse_bcs(&json.proof)?,
+        })
+    }
+}
+
+impl From<DecryptionShare> for DecryptionShareJson {
+    fn from(share: DecryptionShare) -> Self {
+        DecryptionShareJson {
+            index: share.index,
+            partial: Hex::encode(share.partial.to_byte_array()),
+            proof: Hex::encode(bcs::to_bytes(&share.proof).unwrap()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fastcrypto::groups::Scalar as _;
+
+    #[test]
+    fn test_dkg() {
+        let mut rng = rand::thread_rng();
+        let (threshold, parties) = (2, 3);
+        let (commitments, shares): (Vec<_>, Vec<_>) = (1..=parties)
+            .map(|dealer| deal(dealer, threshold, parties, &mut rng).unwrap())
+            .unzip();
+        let shares: Vec<DealtShare> = shares.into_iter().flatten().collect();
+
+        let (key_shares, public_keys): (Vec<_>, Vec<_>) = (1..=parties)
+            .map(|index| finalize(index, &commitments, &shares).unwrap())
+            .unzip();
+        assert!(public_keys.iter().all(|pk| *pk == public_keys[0]));
+        let joint_pk = public_keys[0].master_pk;
+
+        // The joint key is an ordinary pubkey.
+        let msk = G1Element::generator() * Scalar::rand(&mut rng);
+        let (enc, _) = elgamal::encrypt(&msk, &joint_pk, &mut rng);
+        let decryption_shares: Vec<_> = key_shares
+            .iter()
+            .map(|key_share| decryption_share(key_share, &enc, &mut rng))
+            .collect();
+        for pair in [[0, 1], [1, 2], [2, 0]] {
+            let pair: Vec<_> = pair.iter().map(|&i| decryption_shares[i].clone()).collect();
+            assert_eq!(
+                combine_decryption_shares(&public_keys[0], &enc, &pair).unwrap(),
+                msk
+            );
+        }
+        assert!(combine_decryption_shares(&public_keys[0], &enc, &decryption_shares[..1]).is_err());
+
+        // A corrupted share is rejected, and so is the share of party 3 sent to party 2.
+        let mut bad_shares = shares.clone();
+        bad_shares[1].share = Scalar::rand(&mut rng);
+        assert!(finalize(2, &commitments, &bad_shares).is_err());
+        let mut misaddressed = shares.clone();
+        misaddressed[1].share = shares[2].share;
+        assert!(finalize(2, &commitments, &misaddressed).is_err());
+
+        // So is a forged decryption share.
+        let mut forged = decryption_shares[..2].to_vec();
+        forged[0].partial = forged[1].partial;
+        assert!(matches!(
+            combine_decryption_shares(&public_keys[0], &enc, &forged),
+            Err(EnftError::ProofRejected(_))
+        ));
+    }
+
+    #[test]
+    fn test_json() {
+        let (commitment, shares) = deal(1, 2, 3, &mut rand::thread_rng()).unwrap();
+        let json = serde_json::to_string(&commitment).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Commitment>(&json).unwrap(),
+            commitment
+        );
+        let json = serde_json::to_string(&shares[2]).unwrap();
+        assert_eq!(
+            serde_json::from_str::<DealtShare>(&json).unwrap(),
+            shares[2]
+        );
+    }
+}
@@ -0,0 +1,320 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use fastcrypto::groups::bls12381::{reduce_mod_uniform_buffer, G1Element, Scalar};
+use fastcrypto::groups::{GroupElement, Scalar as ScalarTrait};
+use fastcrypto::hash::{Blake2b256, HashFunction, Sha512};
+use fastcrypto::serde_helpers::ToFromByteArray;
+use fastcrypto::traits::AllowedRng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// An encryption of group element m under pk is (r*G, r*pk + m) for random r.
+/// Same as struct ElGamalEncryption in the Move package.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElGamalEncryption {
+    pub ephemeral: G1Element,
+    pub ciphertext: G1Element,
+}
+
+/// A proof that two encrypted master keys are consistent wrt the same master key.
+/// Same as struct EqualityProof in the Move package.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EqualityProof {
+    pub s1: Scalar,    // z1
+    pub s2: Scalar,    // z2
+    pub u1: G1Element, // a1
+    pub u2: G1Element, // a2
+    pub v: G1Element,  // a3
+}
+
+/// A Chaum-Pedersen proof that a partial decryption `eph*sk` was computed with
+/// the private key of `pk = sk*G`, i.e. that log_G(pk) = log_eph(eph*sk).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DleqProof {
+    pub a1: G1Element, // r*G
+    pub a2: G1Element, // r*eph
+    pub z: Scalar,     // r + c*sk
+}
+
+/// The check of a proof that failed during verification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvalidProof {
+    /// z1*G != a1 + c*pk1
+    S1,
+    /// z2*G != a2 + c*eph2
+    S2,
+    /// a3 != c*(ct2 - ct1) + z1*eph1 - z2*pk2
+    V,
+    /// z*G != a1 + c*pk or z*eph != a2 + c*partial
+    Dleq,
+}
+
+impl fmt::Display for InvalidProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidProof::S1 => write!(f, "Invalid Schnorr proof for s1"),
+            InvalidProof::S2 => write!(f, "Invalid Schnorr proof for s2"),
+            InvalidProof::V => write!(f, "Invalid Schnorr proof for v"),
+            InvalidProof::Dleq => write!(f, "Invalid DLEQ proof for the partial decryption"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidProof {}
+
+/// Encrypt the master key under the given pubkey with fresh randomness. The
+/// randomness is returned as well since it is the witness needed later by
+/// [prove_equality].
+pub fn encrypt<R: AllowedRng>(
+    msk: &G1Element,
+    pk: &G1Element,
+    rng: &mut R,
+) -> (ElGamalEncryption, Scalar) {
+    let randomness = Scalar::rand(rng);
+    (encrypt_with_randomness(msk, pk, &randomness), randomness)
+}
+
+/// Encrypt the master key under the given pubkey with the given randomness.
+pub fn encrypt_with_randomness(
+    msk: &G1Element,
+    pk: &G1Element,
+    randomness: &Scalar,
+) -> ElGamalEncryption {
+    ElGamalEncryption {
+        ephemeral: G1Element::generator() * *randomness,
+        ciphertext: *pk * *randomness + *msk,
+    }
+}
+
+/// Decrypt the master key with the private key of the pubkey it was encrypted to.
+pub fn decrypt(enc: &ElGamalEncryption, sk: &Scalar) -> G1Element {
+    enc.ciphertext - enc.ephemeral * *sk
+}
+
+/// Prove that `new_enc` (encrypted under `receiver_pk` with `new_randomness`)
+/// and `prev_enc` (encrypted under the pubkey of `sender_sk`) hold the same
+/// master key.
+pub fn prove_equality<R: AllowedRng>(
+    sender_sk: &Scalar,
+    receiver_pk: &G1Element,
+    prev_enc: &ElGamalEncryption,
+    new_enc: &ElGamalEncryption,
+    new_randomness: &Scalar,
+    rng: &mut R,
+) -> EqualityProof {
+    let gen = G1Element::generator();
+    let sender_pk = gen * *sender_sk;
+
+    let alpha = Scalar::rand(rng);
+    let beta = Scalar::rand(rng);
+
+    let u1 = gen * alpha;
+    let u2 = gen * beta;
+    let v = prev_enc.ephemeral * alpha - *receiver_pk * beta;
+
+    let c = fiat_shamir_challenge(&sender_pk, receiver_pk, prev_enc, new_enc, u1, u2, v);
+
+    EqualityProof {
+        s1: *sender_sk * c + alpha,
+        s2: *new_randomness * c + beta,
+        u1,
+        u2,
+        v,
+    }
+}
+
+/// Verify an equality proof. This mirrors `equality_verify` in the Move package.
+pub fn verify_equality(
+    sender_pk: &G1Element,
+    receiver_pk: &G1Element,
+    prev_enc: &ElGamalEncryption,
+    curr_enc: &ElGamalEncryption,
+    proof: &EqualityProof,
+) -> Result<(), InvalidProof> {
+    let c = fiat_shamir_challenge(
+        sender_pk,
+        receiver_pk,
+        prev_enc,
+        curr_enc,
+        proof.u1,
+        proof.u2,
+        proof.v,
+    );
+    let gen = G1Element::generator();
+
+    if gen * proof.s1 != *sender_pk * c + proof.u1 {
+        return Err(InvalidProof::S1);
+    }
+
+    if gen * proof.s2 != curr_enc.ephemeral * c + proof.u2 {
+        return Err(InvalidProof::S2);
+    }
+
+    if prev_enc.ephemeral * proof.s1 - *receiver_pk * proof.s2
+        != (prev_enc.ciphertext - curr_enc.ciphertext) * c + proof.v
+    {
+        return Err(InvalidProof::V);
+    }
+    Ok(())
+}
+
+/// Re-encrypt the master key under `receiver_pk` and prove that the result is
+/// consistent with `prev_enc`. This is what the seller runs on transfer.
+pub fn transfer<R: AllowedRng>(
+    msk: &G1Element,
+    prev_enc: &ElGamalEncryption,
+    sender_sk: &Scalar,
+    receiver_pk: &G1Element,
+    rng: &mut R,
+) -> (ElGamalEncryption, EqualityProof) {
+    let (new_enc, randomness) = encrypt(msk, receiver_pk, rng);
+    let proof = prove_equality(sender_sk, receiver_pk, prev_enc, &new_enc, &randomness, rng);
+    (new_enc, proof)
+}
+
+/// Compute the partial decryption `eph*sk` of an encryption with the given ephemeral
+/// key, and prove that it used the private key of `sk*G`. This is what a key server
+/// runs so that it never reveals `sk`.
+pub fn partial_decrypt<R: AllowedRng>(
+    ephemeral: &G1Element,
+    sk: &Scalar,
+    rng: &mut R,
+) -> (G1Element, DleqProof) {
+    let gen = G1Element::generator();
+    let pk = gen * *sk;
+    let partial = *ephemeral * *sk;
+
+    let r = Scalar::rand(rng);
+    let a1 = gen * r;
+    let a2 = *ephemeral * r;
+    let c = dleq_challenge(&pk, ephemeral, &partial, &a1, &a2);
+    (
+        partial,
+        DleqProof {
+            a1,
+            a2,
+            z: r + c * *sk,
+        },
+    )
+}
+
+/// Verify that `partial` is the partial decryption of `ephemeral` with the private key of `pk`.
+pub fn verify_partial_decryption(
+    pk: &G1Element,
+    ephemeral: &G1Element,
+    partial: &G1Element,
+    proof: &DleqProof,
+) -> Result<(), InvalidProof> {
+    let c = dleq_challenge(pk, ephemeral, partial, &proof.a1, &proof.a2);
+    if G1Element::generator() * proof.z != proof.a1 + *pk * c
+        || *ephemeral * proof.z != proof.a2 + *partial * c
+    {
+        return Err(InvalidProof::Dleq);
+    }
+    Ok(())
+}
+
+/// Decrypt the master key given the partial decryption `eph*sk` of its ephemeral key.
+pub fn decrypt_with_partial(enc: &ElGamalEncryption, partial: &G1Element) -> G1Element {
+    enc.ciphertext - *partial
+}
+
+fn dleq_challenge(
+    pk: &G1Element,
+    ephemeral: &G1Element,
+    partial: &G1Element,
+    a1: &G1Element,
+    a2: &G1Element,
+) -> Scalar {
+    let mut hash = Sha512::new();
+    hash.update(b"enft-dleq");
+    for element in [&G1Element::generator(), pk, ephemeral, partial, a1, a2] {
+        hash.update(element.to_byte_array());
+    }
+    reduce_mod_uniform_buffer(&hash.finalize().digest)
+}
+
+pub fn fiat_shamir_challenge(
+    pk1: &G1Element,
+    pk2: &G1Element,
+    enc1: &ElGamalEncryption,
+    enc2: &ElGamalEncryption,
+    a1: G1Element,
+    a2: G1Element,
+    a3: G1Element,
+) -> Scalar {
+    let mut fiat_shamir_msg = Blake2b256::new();
+    fiat_shamir_msg.update(pk1.to_byte_array());
+    fiat_shamir_msg.update(pk2.to_byte_array());
+    fiat_shamir_msg.update(enc1.ephemeral.to_byte_array());
+    fiat_shamir_msg.update(enc1.ciphertext.to_byte_array());
+    fiat_shamir_msg.update(enc2.ephemeral.to_byte_array());
+    fiat_shamir_msg.update(enc2.ciphertext.to_byte_array());
+    fiat_shamir_msg.update(a1.to_byte_array());
+    fiat_shamir_msg.update(a2.to_byte_array());
+    fiat_shamir_msg.update(a3.to_byte_array());
+
+    // Scalars are big-endian, so clearing the first byte keeps the challenge in the
+    // field. Must match fiat_shamir_challenge in the Move package byte-for-byte.
+    let mut digest = fiat_shamir_msg.finalize().digest;
+    digest[0] = 0;
+    Scalar::from_byte_array(&digest).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_transfer_and_verify() {
+        let mut rng = rand::thread_rng();
+        let gen = G1Element::generator();
+        let msk = gen * Scalar::rand(&mut rng);
+        let seller_sk = Scalar::rand(&mut rng);
+        let buyer_sk = Scalar::rand(&mut rng);
+        let seller_pk = gen * seller_sk;
+        let buyer_pk = gen * buyer_sk;
+
+        let (prev_enc, _) = encrypt(&msk, &seller_pk, &mut rng);
+        assert_eq!(decrypt(&prev_enc, &seller_sk), msk);
+
+        let (new_enc, proof) = transfer(&msk, &prev_enc, &seller_sk, &buyer_pk, &mut rng);
+        assert_eq!(decrypt(&new_enc, &buyer_sk), msk);
+        assert!(verify_equality(&seller_pk, &buyer_pk, &prev_enc, &new_enc, &proof).is_ok());
+
+        // A re-encryption of a different master key must be rejected.
+        let other_msk = gen * Scalar::rand(&mut rng);
+        let (bad_enc, bad_proof) = transfer(&other_msk, &prev_enc, &seller_sk, &buyer_pk, &mut rng);
+        assert_eq!(
+            verify_equality(&seller_pk, &buyer_pk, &prev_enc, &bad_enc, &bad_proof),
+            Err(InvalidProof::V)
+        );
+    }
+
+    #[test]
+    fn test_partial_decryption() {
+        let mut rng = rand::thread_rng();
+        let gen = G1Element::generator();
+        let msk = gen * Scalar::rand(&mut rng);
+        let sk = Scalar::rand(&mut rng);
+        let pk = gen * sk;
+        let (enc, _) = encrypt(&msk, &pk, &mut rng);
+
+        let (partial, proof) = partial_decrypt(&enc.ephemeral, &sk, &mut rng);
+        assert!(verify_partial_decryption(&pk, &enc.ephemeral, &partial, &proof).is_ok());
+        assert_eq!(decrypt_with_partial(&enc, &partial), msk);
+
+        // A partial decryption with another key must be rejected.
+        let (bad_partial, bad_proof) =
+            partial_decrypt(&enc.ephemeral, &Scalar::rand(&mut rng), &mut rng);
+        assert_eq!(
+            verify_partial_decryption(&pk, &enc.ephemeral, &bad_partial, &bad_proof),
+            Err(InvalidProof::Dleq)
+        );
+        assert_eq!(
+            verify_partial_decryption(&pk, &enc.ephemeral, &bad_partial, &proof),
+            Err(InvalidProof::Dleq)
+        );
+    }
+}
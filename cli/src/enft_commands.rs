@@ -3,33 +3,71 @@
 
 use bip32::DerivationPath;
 use bip39::{Language, Mnemonic, MnemonicType};
-use clap::Parser;
-use enft_cli::key_derive::derive_key;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use enft_cli::blob::{read_bcs, read_blob, read_g1_element, BlobEncoding};
+use enft_cli::dkg;
+use enft_cli::dkg::{Commitment, DealtShare, DecryptionShare};
+use enft_cli::elgamal;
+use enft_cli::elgamal::{ElGamalEncryption, EqualityProof};
+use enft_cli::error::{EnftError, EnftResult};
+use enft_cli::inspect::inspect;
 use enft_cli::key_derive::derive_private_key;
+use enft_cli::key_derive::{derive_key, DerivationScheme};
+use enft_cli::keyserver::{KeyServerClient, ThresholdClient};
+use enft_cli::keystore::{KeyKind, Keystore, KEYSTORE_ENV};
+use enft_cli::marketplace::{NewArgs, TransferToArgs};
+use enft_cli::move_call::{parse_address, PureArg, NEW_FUNCTION, TRANSFER_TO_FUNCTION};
+use enft_cli::region::{mask_from_image, ChunkGeometry, Region, RegionSpec, CHUNK_SIZE};
+use enft_cli::sampling::BuiltinStrategy;
+use enft_cli::style::ObfuscationStyle;
+use enft_cli::threshold::{deal, KeyShare, ThresholdPublicKey};
+use enft_cli::utils::g1_element_from_bytes;
 use enft_cli::utils::load_and_sample_image;
 use enft_cli::utils::load_image;
-use enft_cli::utils::msk_to_cipher;
+use enft_cli::utils::obfuscate_image;
+use enft_cli::utils::read_ciphertext;
 use enft_cli::utils::recover_image;
+use enft_cli::utils::recover_master_key;
 use enft_cli::utils::save_image;
+use enft_cli::utils::scalar_from_bytes;
+use enft_cli::utils::write_ciphertext;
+use enft_cli::vectors;
 use enft_cli::FullCipherText;
-use fastcrypto::aes::Cipher;
-use fastcrypto::aes::InitializationVector;
-use fastcrypto::encoding::{Encoding, Hex};
 use fastcrypto::groups::bls12381::{G1Element, Scalar};
-use fastcrypto::hash::Blake2b256;
+use fastcrypto::groups::{GroupElement, Scalar as ScalarTrait};
 use fastcrypto::serde_helpers::ToFromByteArray;
-use fastcrypto::traits::Generate;
-use fastcrypto::{
-    groups::{GroupElement, Scalar as ScalarTrait},
-    hash::HashFunction,
-};
-use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use typenum::U12;
 
 #[derive(Parser)]
 #[command(name = "enft-cli")]
 #[command(about = "Offchain utilities for key generating, encrypting and decrypting NFTs", long_about = None)]
+struct Cli {
+    /// The output format: lines of text, or one JSON object per command with stable
+    /// field names. Errors are printed as `{"error": ..., "exit_code": ...}` in JSON.
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// The encoding of blobs such as keys, encrypted master keys, proofs and the
+    /// ciphertext file: hex, base64 or raw. Blob arguments can also be given as
+    /// `@path` to read a file or `-` to read stdin. Blobs given inline or printed
+    /// are hex encoded when raw is chosen.
+    #[clap(long, default_value_t = BlobEncoding::default())]
+    encoding: BlobEncoding,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand)]
 enum Command {
     /// Generate a master key. This can be used by the creator only.
     GenerateMasterKey,
@@ -48,6 +86,19 @@ enum Command {
     /// derive encryption keys based on unique app id and user id.
     DeriveEncryptionKey(DeriveArgs),
 
+    /// Split a fresh key server master secret into shares for a threshold key
+    /// server. Writes `share-<i>.json` for each server and `public.json` with the
+    /// threshold public key. The master secret itself is never written.
+    DealKeyShares(DealArgs),
+
+    /// Generate a joint encryption key for k-of-n co-owners with a distributed key
+    /// generation, and decrypt with it. The joint pubkey can be used as `--enc-pk`
+    /// and `--buyer-pk` like any other, but no single party learns its secret.
+    Dkg {
+        #[command(subcommand)]
+        command: DkgCommand,
+    },
+
     /// Encrypt the master key under the given pubkey. Output encrypted
     /// master key (enc_msk) and ciphertext. This is done by creator when
     /// listing the NFT. The obfuscated NFT, enc_msk and ciphertext are
@@ -66,6 +117,77 @@ enum Command {
     /// Given a proof, the previous encryption and its pubkey (seller's pk),
     /// the current encryption and its pubkey (buyer's pk), verify the proof.
     Verify(VerifyArgs),
+
+    /// Decode an encrypted master key, an equality proof or a ciphertext and check
+    /// that its points are valid non-identity G1 elements, and that the size of a
+    /// ciphertext matches its region. Exits with an error if a check fails.
+    Inspect(InspectArgs),
+
+    /// Print the pure arguments of the `private_nft` entry functions in the order of
+    /// the Move parameters, as BCS bytes for a transaction or as JSON values for the
+    /// TypeScript SDK.
+    CallArgs {
+        #[command(subcommand)]
+        command: CallArgsCommand,
+    },
+
+    /// Generate deterministic keys, encryptions and equality proofs, valid and
+    /// deliberately invalid ones, for checking other verifiers against this prover.
+    /// Writes `vectors.json` and `vectors.move` with Move `#[test]` functions to paste
+    /// into the `private_nft` module.
+    GenVectors(GenVectorsArgs),
+
+    /// Manage the password-encrypted keystore. Keys stored there can be used
+    /// with `--key <name>` instead of passing secrets on the command line.
+    Keys(KeysArgs),
+}
+
+#[derive(Args, Clone)]
+struct KeyArgs {
+    /// The name of a keystore entry to use instead of a secret argument. Entries are
+    /// matched to secrets by their kind, so this can be given once per secret.
+    #[clap(long = "key")]
+    keys: Vec<String>,
+
+    /// The keystore file. Defaults to `~/.enft/keystore.json`.
+    #[clap(long, env = KEYSTORE_ENV)]
+    keystore: Option<PathBuf>,
+}
+
+#[derive(Parser, Clone)]
+struct KeysArgs {
+    /// The keystore file. Defaults to `~/.enft/keystore.json`.
+    #[clap(long, env = KEYSTORE_ENV)]
+    keystore: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: KeysCommand,
+}
+
+#[derive(Subcommand, Clone)]
+enum KeysCommand {
+    /// Store a key under a new name. The hex encoded secret is read from a
+    /// prompt unless `--generate` is given.
+    Add {
+        name: String,
+
+        /// The kind of key: encryption, master or derivation.
+        #[clap(long, default_value_t = KeyKind::Encryption)]
+        kind: KeyKind,
+
+        /// Generate a fresh key instead of importing one.
+        #[clap(long)]
+        generate: bool,
+    },
+
+    /// List the stored keys with their kinds and public keys.
+    List,
+
+    /// Print the hex encoded secret of a stored key.
+    Export { name: String },
+
+    /// Delete a stored key.
+    Remove { name: String },
 }
 
 #[derive(Parser, Clone)]
@@ -77,13 +199,66 @@ struct GenerateArgs {
     /// A valid derivation path in this form: m/94'/784'/{account}'/{change}'/{address}'.
     #[clap(short, long)]
     derivation_path: Option<DerivationPath>,
+
+    /// The number of words of generated mnemonics: 12, 15, 18, 21 or 24.
+    #[clap(long, default_value = "12", value_parser = parse_word_count, conflicts_with = "mnemonics")]
+    words: MnemonicType,
+
+    /// The wordlist of the mnemonics.
+    #[clap(long, value_enum, default_value_t = WordlistLanguage::English)]
+    language: WordlistLanguage,
+
+    /// Use a BIP39 passphrase, read from `ENFT_BIP39_PASSPHRASE` or a prompt.
+    #[clap(long)]
+    passphrase: bool,
+
+    /// The key derivation scheme: sip23 (the original scheme) or eip2333.
+    #[clap(long, default_value_t = DerivationScheme::default())]
+    scheme: DerivationScheme,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum WordlistLanguage {
+    English,
+    ChineseSimplified,
+    ChineseTraditional,
+    French,
+    Italian,
+    Japanese,
+    Korean,
+    Spanish,
+}
+
+impl From<WordlistLanguage> for Language {
+    fn from(language: WordlistLanguage) -> Self {
+        match language {
+            WordlistLanguage::English => Language::English,
+            WordlistLanguage::ChineseSimplified => Language::ChineseSimplified,
+            WordlistLanguage::ChineseTraditional => Language::ChineseTraditional,
+            WordlistLanguage::French => Language::French,
+            WordlistLanguage::Italian => Language::Italian,
+            WordlistLanguage::Japanese => Language::Japanese,
+            WordlistLanguage::Korean => Language::Korean,
+            WordlistLanguage::Spanish => Language::Spanish,
+        }
+    }
+}
+
+fn parse_word_count(s: &str) -> Result<MnemonicType, String> {
+    s.parse::<usize>()
+        .ok()
+        .and_then(|count| MnemonicType::for_word_count(count).ok())
+        .ok_or_else(|| format!("Invalid word count {}, expected 12, 15, 18, 21 or 24", s))
 }
 
 #[derive(Parser, Clone)]
 struct DeriveArgs {
     /// The 32-byte master secret key encoded in hex held securely by the server.
-    #[clap(short, long)]
-    master_key: String,
+    #[clap(short, long, required_unless_present = "keys")]
+    master_key: Option<String>,
+
+    #[command(flatten)]
+    key: KeyArgs,
 
     /// The app_id for the application (example: `iss_len || iss || aud_len || aud`).
     #[clap(short, long)]
@@ -94,6 +269,91 @@ struct DeriveArgs {
     user_id: String,
 }
 
+#[derive(Parser, Clone)]
+struct DealArgs {
+    /// The number of servers needed to decrypt.
+    #[clap(short, long, value_parser = clap::value_parser!(u16).range(1..))]
+    threshold: u16,
+
+    /// The number of servers to create shares for.
+    #[clap(short, long, value_parser = clap::value_parser!(u16).range(1..))]
+    shares: u16,
+
+    /// The directory to write the shares and the public key to. Created if missing.
+    /// Defaults to the current directory.
+    #[clap(long)]
+    out_dir: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Clone)]
+enum DkgCommand {
+    /// Deal a random polynomial as one of the parties. Writes the commitment
+    /// `commitment-<index>.json` to broadcast to all parties, and
+    /// `share-<index>-for-<j>.json` to send to party j over a private channel.
+    Deal {
+        /// The index of this party, from 1 to the number of parties.
+        #[clap(long)]
+        index: u16,
+
+        /// The number of parties needed to decrypt.
+        #[clap(short, long)]
+        threshold: u16,
+
+        /// The number of parties.
+        #[clap(short, long)]
+        parties: u16,
+
+        /// The directory to write to. Created if missing. Defaults to the current
+        /// directory.
+        #[clap(long)]
+        out_dir: Option<PathBuf>,
+    },
+
+    /// Check the shares received from the other parties against their commitments
+    /// and compute this party's share of the joint key. Writes `key-share-<index>.json`
+    /// and `public.json` with the joint pubkey, which all parties must agree on.
+    Finalize {
+        /// The index of this party.
+        #[clap(long)]
+        index: u16,
+
+        /// The commitment of a party. All parties must give the same commitments.
+        #[clap(long = "commitment", required = true)]
+        commitments: Vec<PathBuf>,
+
+        /// A share dealt to this party, one from each party with a commitment.
+        #[clap(long = "share", required = true)]
+        shares: Vec<PathBuf>,
+
+        /// The directory to write to. Created if missing. Defaults to the current
+        /// directory.
+        #[clap(long)]
+        out_dir: Option<PathBuf>,
+    },
+
+    /// Partially decrypt a master key encrypted under the joint pubkey with this
+    /// party's key share. Pass the decryption shares of enough parties to `decrypt`.
+    PartialDecrypt {
+        /// The key share written by `dkg finalize`.
+        #[clap(long)]
+        key_share: PathBuf,
+
+        /// An encrypted master key under the joint pubkey.
+        #[clap(short, long)]
+        enc_master_sk: String,
+
+        /// Where to write the decryption share. Defaults to
+        /// `decryption-share-<index>.json` in the output directory.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+
+        /// The directory to write to when no explicit path is given. Created if
+        /// missing. Defaults to the current directory.
+        #[clap(long)]
+        out_dir: Option<PathBuf>,
+    },
+}
+
 #[derive(Parser, Clone)]
 struct EncryptArgs {
     /// A path for the original file.
@@ -101,19 +361,66 @@ struct EncryptArgs {
     image_path: String,
 
     /// A hex encoding of the master private key to encrypt with.
-    #[clap(short, long)]
-    master_sk: String,
+    #[clap(short, long, required_unless_present = "keys")]
+    master_sk: Option<String>,
+
+    #[command(flatten)]
+    key: KeyArgs,
 
     /// A hex encoding of the pubkey to encrypt with.
     #[clap(short, long)]
     enc_pk: String,
+
+    /// How to choose the chunks to obfuscate: count:<n>, percent:<p>, checkerboard
+    /// or seeded:<seed>:<n>. The strategy is recorded in the ciphertext.
+    #[clap(long, default_value_t = BuiltinStrategy::default())]
+    strategy: BuiltinStrategy,
+
+    /// The width in pixels of the chunks that the image is divided into for sampling.
+    #[clap(long, default_value_t = CHUNK_SIZE, value_parser = clap::value_parser!(u32).range(1..))]
+    chunk_width: u32,
+
+    /// The height in pixels of the chunks that the image is divided into for sampling.
+    #[clap(long, default_value_t = CHUNK_SIZE, value_parser = clap::value_parser!(u32).range(1..))]
+    chunk_height: u32,
+
+    /// How the obfuscated pixels look in the public image: transparent, solid:<rrggbb>,
+    /// pixelate:<block>, blur:<sigma>, noise or mean. The original pixels are encrypted
+    /// and restored exactly regardless of the style.
+    #[clap(long, default_value_t = ObfuscationStyle::default())]
+    style: ObfuscationStyle,
+
+    /// A mask image with the same dimensions as the original. Pixels that are
+    /// neither black nor transparent are obfuscated instead of sampled chunks.
+    #[clap(long, conflicts_with_all = ["strategy", "regions"])]
+    mask: Option<PathBuf>,
+
+    /// A JSON region spec with rectangles and polygons in pixel coordinates to
+    /// obfuscate instead of sampled chunks.
+    #[clap(long, conflicts_with = "strategy")]
+    regions: Option<PathBuf>,
+
+    /// Where to write the ciphertext. Defaults to `ciphertext` in the output directory.
+    #[clap(long)]
+    ciphertext_out: Option<PathBuf>,
+
+    /// Where to write the obfuscated image. Defaults to `obfuscated_nft.png` in the
+    /// output directory.
+    #[clap(long)]
+    obfuscated_out: Option<PathBuf>,
+
+    /// The directory to write outputs to when no explicit path is given. Created if
+    /// missing. Defaults to the current directory.
+    #[clap(long)]
+    out_dir: Option<PathBuf>,
 }
 
 #[derive(Parser, Clone)]
 struct TransferArgs {
-    /// A hex encoding of the master private key to encrypt with.
-    #[clap(short, long)]
-    master_sk: String,
+    /// A hex encoding of the master private key to encrypt with. Resellers can
+    /// leave this out and give the listing's ciphertext instead.
+    #[clap(short, long, required_unless_present_any = ["ciphertext_path", "keys"])]
+    master_sk: Option<String>,
     /// An encrypted master key under the seller's pubkey.
     #[clap(short, long)]
     prev_enc_msk: String,
@@ -121,8 +428,20 @@ struct TransferArgs {
     #[clap(short, long)]
     buyer_pk: String,
     /// A hex encoding of the seller's encryption private key.
-    #[clap(short, long)]
-    seller_enc_sk: String,
+    #[clap(short, long, required_unless_present = "keys")]
+    seller_enc_sk: Option<String>,
+
+    #[command(flatten)]
+    key: KeyArgs,
+
+    /// A path to the ciphertext of the NFT. Without `--master-sk`, the master key is
+    /// recovered from `prev_enc_msk` with the seller's key and checked against it.
+    #[clap(short, long, conflicts_with = "master_sk")]
+    ciphertext_path: Option<PathBuf>,
+
+    /// A path to the obfuscated image that the ciphertext belongs to.
+    #[clap(long, default_value = "obfuscated_nft.png")]
+    obfuscated_in: PathBuf,
 }
 
 #[derive(Parser, Clone)]
@@ -136,8 +455,135 @@ struct DecryptArgs {
     ciphertext_path: String,
 
     /// A hex encoding of the buyer's private key.
+    #[clap(short, long, required_unless_present_any = ["keys", "via_keyserver", "decryption_shares"])]
+    buyer_sk: Option<String>,
+
+    #[command(flatten)]
+    key: KeyArgs,
+
+    /// The URL of a key server holding the buyer's key, to decrypt the master key with
+    /// instead of `--buyer-sk`. The server's proof is checked against `--buyer-pk`.
+    /// With `--threshold-public`, give this once per threshold key server.
+    #[clap(long, requires = "buyer_pk", conflicts_with_all = ["buyer_sk", "keys"])]
+    via_keyserver: Vec<String>,
+
+    /// The `public.json` of a threshold key server, to combine partial decryptions
+    /// from several `--via-keyserver` servers.
+    #[clap(long, requires = "via_keyserver")]
+    threshold_public: Option<PathBuf>,
+
+    /// A decryption share written by `dkg partial-decrypt`, to decrypt a master key
+    /// encrypted under a joint pubkey. Give this once per co-owner.
+    #[clap(long = "decryption-share", requires = "joint_public", conflicts_with_all = ["buyer_sk", "keys", "via_keyserver"])]
+    decryption_shares: Vec<PathBuf>,
+
+    /// The `public.json` written by `dkg finalize`, to check the decryption shares.
+    #[clap(long, requires = "decryption_shares")]
+    joint_public: Option<PathBuf>,
+
+    /// A hex encoding of the buyer's published pubkey, for `--via-keyserver`.
+    #[clap(long)]
+    buyer_pk: Option<String>,
+
+    /// The JWT to authenticate with the key server.
+    #[clap(long, env = "ENFT_KEYSERVER_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+
+    /// A path to the obfuscated image.
+    #[clap(long, default_value = "obfuscated_nft.png")]
+    obfuscated_in: PathBuf,
+
+    /// Where to write the recovered image. Defaults to `original_nft.png` in the
+    /// output directory.
     #[clap(short, long)]
-    buyer_sk: String,
+    output: Option<PathBuf>,
+
+    /// The directory to write the recovered image to when no explicit path is given.
+    /// Created if missing. Defaults to the current directory.
+    #[clap(long)]
+    out_dir: Option<PathBuf>,
+}
+
+#[derive(Parser, Clone)]
+struct InspectArgs {
+    /// The blob to inspect, e.g. `@ciphertext`.
+    blob: String,
+}
+
+#[derive(Subcommand, Clone)]
+enum CallArgsCommand {
+    /// The arguments of `private_nft::new` for listing an NFT.
+    New {
+        /// The name of the NFT.
+        #[clap(long)]
+        name: String,
+
+        /// The URL of the obfuscated image.
+        #[clap(long)]
+        image_url: String,
+
+        /// The URL of the ciphertext.
+        #[clap(long)]
+        ciphertext_url: String,
+
+        /// The encrypted master key printed by `encrypt`.
+        #[clap(short, long)]
+        enc_master_sk: String,
+
+        #[clap(long, value_enum, default_value_t = CallArgsFormat::Bcs)]
+        format: CallArgsFormat,
+    },
+
+    /// The arguments of `private_nft::transfer_to` after the NFT object, from the
+    /// output of `transfer`.
+    TransferTo {
+        /// The address of the buyer.
+        #[clap(long)]
+        to: String,
+
+        /// A serialized consistency proof.
+        #[clap(long)]
+        serialized_proof: String,
+
+        /// Previous encrypted master key under seller's pubkey.
+        #[clap(short, long)]
+        prev_enc_msk: String,
+
+        /// Current encrypted master key under buyer's pubkey.
+        #[clap(short, long)]
+        curr_enc_msk: String,
+
+        /// The seller's encryption pubkey.
+        #[clap(short, long)]
+        seller_enc_pk: String,
+
+        /// The buyer's encryption pubkey.
+        #[clap(short, long)]
+        buyer_enc_pk: String,
+
+        #[clap(long, value_enum, default_value_t = CallArgsFormat::Bcs)]
+        format: CallArgsFormat,
+    },
+}
+
+/// How `call-args` prints the arguments in text mode.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum CallArgsFormat {
+    /// One line per argument with its name, Move type and BCS bytes.
+    Bcs,
+    /// A JSON array of the values to pass to `tx.pure`.
+    Json,
+}
+
+#[derive(Parser, Clone)]
+struct GenVectorsArgs {
+    /// The seed of the vectors. The same seed always gives the same vectors.
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+
+    /// The directory to write to. Created if missing. Defaults to the current directory.
+    #[clap(long)]
+    out_dir: Option<PathBuf>,
 }
 
 #[derive(Parser, Clone)]
@@ -163,227 +609,482 @@ struct VerifyArgs {
     buyer_enc_pk: String,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-pub struct ElGamalEncryption {
-    pub ephemeral: G1Element,
-    pub ciphertext: G1Element,
-}
-
-/// A proof that two encrypted master keys are consistent wrt the same master key.
-#[derive(Clone, Serialize, Deserialize)]
-pub struct EqualityProof {
-    pub s1: Scalar,    //z1
-    pub s2: Scalar,    // z2
-    pub u1: G1Element, // a1
-    pub u2: G1Element, // a2
-    pub v: G1Element,  // a3
-}
-
 fn main() {
-    match execute(Command::parse()) {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) if e.use_stderr() && json_output_requested(std::env::args_os()) => {
+            let error = serde_json::json!({
+                "error": e.to_string().trim_end(),
+                "exit_code": e.exit_code(),
+            });
+            println!("{}", error);
+            std::process::exit(e.exit_code());
+        }
+        // Help and version are printed as usual.
+        Err(e) => e.exit(),
+    };
+    let mut output = Output::new(cli.output);
+    match execute(cli.command, cli.encoding, &mut output) {
         Ok(_) => {
+            output.finish();
             std::process::exit(exitcode::OK);
         }
         Err(e) => {
-            println!("Error: {}", e);
-            std::process::exit(exitcode::DATAERR);
+            output.error(&e);
+            std::process::exit(e.exit_code());
+        }
+    }
+}
+
+/// Whether `--output json` is given before the subcommand, so that errors of
+/// parsing the rest of the arguments can be printed as JSON too.
+fn json_output_requested(args: impl Iterator<Item = std::ffi::OsString>) -> bool {
+    let mut json = false;
+    let mut args = args.skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--output") => json = args.next().is_some_and(|value| value == "json"),
+            Some(arg) if arg.starts_with("--output=") => json = arg == "--output=json",
+            Some("--encoding") => {
+                args.next();
+            }
+            Some(arg) if arg.starts_with("--") => {}
+            // The subcommand, whose own `--output` is a path.
+            _ => break,
+        }
+    }
+    json
+}
+
+/// What a command prints: lines of text, or with `--output json` a single JSON
+/// object that is printed when the command is done.
+struct Output {
+    format: OutputFormat,
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Output {
+    fn new(format: OutputFormat) -> Self {
+        Output {
+            format,
+            fields: serde_json::Map::new(),
+        }
+    }
+
+    /// Print a line of text, or record the value under the key.
+    fn print(&mut self, key: &str, value: impl serde::Serialize, text: impl std::fmt::Display) {
+        self.text(text);
+        self.value(key, value);
+    }
+
+    /// Print a line in text mode only.
+    fn text(&self, text: impl std::fmt::Display) {
+        if self.format == OutputFormat::Text {
+            println!("{}", text);
+        }
+    }
+
+    /// Print a multi-line value as text, or record all of its fields.
+    fn object(&mut self, value: impl serde::Serialize, text: impl std::fmt::Display) {
+        self.text(text);
+        if let serde_json::Value::Object(fields) = serde_json::to_value(value).unwrap() {
+            self.fields.extend(fields);
+        }
+    }
+
+    /// Record a value in JSON mode only.
+    fn value(&mut self, key: &str, value: impl serde::Serialize) {
+        if self.format == OutputFormat::Json {
+            self.fields
+                .insert(key.to_string(), serde_json::to_value(value).unwrap());
+        }
+    }
+
+    fn finish(self) {
+        if self.format == OutputFormat::Json {
+            println!("{}", serde_json::Value::Object(self.fields));
+        }
+    }
+
+    /// Print the error. In JSON mode it is added to the fields recorded so far.
+    fn error(mut self, e: &EnftError) {
+        match self.format {
+            OutputFormat::Text => println!("Error: {}", e),
+            OutputFormat::Json => {
+                self.value("error", e.to_string());
+                self.value("exit_code", e.exit_code());
+                self.finish();
+            }
         }
     }
 }
 
-fn execute(cmd: Command) -> Result<(), std::io::Error> {
+fn execute(cmd: Command, encoding: BlobEncoding, output: &mut Output) -> EnftResult<()> {
     match cmd {
         Command::GenerateMasterKey => {
             let scalar: Scalar = Scalar::rand(&mut rand::thread_rng());
             let gen = G1Element::generator();
             let g1 = gen * scalar;
-            let msk = Hex::encode(g1.to_byte_array());
-            println!("Master sk: {}", msk);
+            let msk = encoding.encode_text(&g1.to_byte_array());
+            output.print("master_key", &msk, format!("Master sk: {}", msk));
             Ok(())
         }
         Command::GenerateEncryptionKey => {
             let sk: Scalar = Scalar::rand(&mut rand::thread_rng());
             let gen = G1Element::generator();
             let pk = gen * sk;
-            let pk_str = Hex::encode(pk.to_byte_array());
-            let sk_str = Hex::encode(sk.to_byte_array());
-            println!("Encryption sk: {}", sk_str);
-            println!("Encryption pk: {}", pk_str);
+            let pk_str = encoding.encode_text(&pk.to_byte_array());
+            let sk_str = encoding.encode_text(&sk.to_byte_array());
+            output.print("private_key", &sk_str, format!("Encryption sk: {}", sk_str));
+            output.print("public_key", &pk_str, format!("Encryption pk: {}", pk_str));
             Ok(())
         }
         Command::Encrypt(args) => {
-            let msk = G1Element::from_byte_array(
-                &Hex::decode(&args.master_sk).unwrap().try_into().unwrap(),
-            )
-            .unwrap();
-            let enc_pk =
-                G1Element::from_byte_array(&Hex::decode(&args.enc_pk).unwrap().try_into().unwrap())
-                    .unwrap();
+            let msk = g1_element_from_bytes(secret(
+                args.master_sk.as_deref(),
+                &args.key,
+                KeyKind::Master,
+                encoding,
+            )?)?;
+            let enc_pk = read_g1_element(&args.enc_pk, encoding)?;
             let mut rng = rand::thread_rng();
 
             // 1. Encrypt the master key under the given pubkey.
-            let gen = <G1Element as GroupElement>::generator();
-            let encryption_randomness = <G1Element as GroupElement>::ScalarType::rand(&mut rng);
-            let encrypted_msk = ElGamalEncryption {
-                ephemeral: gen * encryption_randomness,
-                ciphertext: enc_pk * encryption_randomness + msk,
-            };
-            let encrypted_msk = Hex::encode(bcs::to_bytes(&encrypted_msk).unwrap());
-            println!("Encrypted master sk:");
-            println!("{:?}", encrypted_msk);
-
-            // 2. Generate the ciphertext.
-            // First initialize an AES cipher from the seed deterministically derived from the master key.
-            // i.e. the AES encryption key is derived from the master key.
-            let cipher = msk_to_cipher(&msk);
-
-            let preprocessed = load_and_sample_image(args.image_path.as_str());
-            println!("Selected pixels: {:?}", preprocessed.selected_coordinates);
-            let iv = InitializationVector::<U12>::generate(&mut rng);
-            let data = cipher.encrypt(&iv, preprocessed.selected_values.as_slice());
-            let full_ciphertext = FullCipherText {
-                pixels: preprocessed.selected_coordinates,
-                iv,
-                data,
+            let (encrypted_msk, _) = elgamal::encrypt(&msk, &enc_pk, &mut rng);
+            let encrypted_msk = encoding.encode_text(&bcs::to_bytes(&encrypted_msk).unwrap());
+            output.print(
+                "encrypted_msk",
+                &encrypted_msk,
+                format!("Encrypted master sk:\n{:?}", encrypted_msk),
+            );
+
+            // 2. Obfuscate the image.
+            let preprocessed = match (&args.mask, &args.regions) {
+                (Some(mask), _) => {
+                    let image = load_image(&args.image_path)?;
+                    let region = mask_from_image(mask, image.width, image.height)?;
+                    obfuscate_image(&image, region, &args.style)?
+                }
+                (None, Some(regions)) => {
+                    let image = load_image(&args.image_path)?;
+                    let region = Region::Shapes(RegionSpec::from_json_file(regions)?);
+                    obfuscate_image(&image, region, &args.style)?
+                }
+                (None, None) => {
+                    let geometry = ChunkGeometry {
+                        width: args.chunk_width,
+                        height: args.chunk_height,
+                    };
+                    load_and_sample_image(&args.image_path, &args.strategy, geometry, &args.style)?
+                }
             };
-            let contents = Hex::encode(bcs::to_bytes(&full_ciphertext).unwrap());
-            std::fs::write("ciphertext", contents)?;
-            println!("Ciphertext written to file.");
+            match &preprocessed.region {
+                Region::Chunks {
+                    geometry,
+                    indices,
+                    strategy,
+                } => {
+                    output.print(
+                        "strategy",
+                        strategy,
+                        format!(
+                            "Sampling strategy: {} with {}x{} chunks",
+                            strategy, geometry.width, geometry.height
+                        ),
+                    );
+                    output.value("chunk_width", geometry.width);
+                    output.value("chunk_height", geometry.height);
+                    output.print(
+                        "selected_chunks",
+                        indices,
+                        format!("Selected pixels: {:?}", indices),
+                    );
+                }
+                Region::Mask(runs) => {
+                    let pixels = runs.iter().map(|(_, len)| len).sum::<u64>();
+                    output.print(
+                        "selected_pixels",
+                        pixels,
+                        format!("Mask selected {} pixels.", pixels),
+                    );
+                }
+                Region::Shapes(spec) => {
+                    output.print(
+                        "rectangle_count",
+                        spec.rectangles.len(),
+                        format!(
+                            "Region spec selected {} rectangles and {} polygons.",
+                            spec.rectangles.len(),
+                            spec.polygons.len()
+                        ),
+                    );
+                    output.value("polygon_count", spec.polygons.len());
+                }
+            }
+
+            // 3. Generate the ciphertext with an AES key deterministically derived from the
+            // master key. The header with the region and a hash of the obfuscated image is
+            // authenticated along with it.
+            let full_ciphertext = FullCipherText::encrypt(&preprocessed, &msk, &mut rng)?;
+            let ciphertext_out =
+                output_path(args.ciphertext_out, args.out_dir.as_deref(), "ciphertext")?;
+            write_ciphertext(
+                &mut File::create(&ciphertext_out)?,
+                &full_ciphertext,
+                encoding,
+            )?;
+            output.print(
+                "ciphertext_path",
+                ciphertext_out.display().to_string(),
+                format!("Ciphertext written to {}.", ciphertext_out.display()),
+            );
 
-            save_image("obfuscated_nft.png", &preprocessed.obfuscated_image);
-            println!("Obfuscated image to file.");
+            let obfuscated_out = output_path(
+                args.obfuscated_out,
+                args.out_dir.as_deref(),
+                "obfuscated_nft.png",
+            )?;
+            save_image(&obfuscated_out, &preprocessed.obfuscated_image)?;
+            output.print(
+                "obfuscated_path",
+                obfuscated_out.display().to_string(),
+                format!("Obfuscated image written to {}.", obfuscated_out.display()),
+            );
             Ok(())
         }
         Command::Transfer(args) => {
-            let prev_enc_msk: ElGamalEncryption =
-                bcs::from_bytes(&Hex::decode(&args.prev_enc_msk).unwrap()).unwrap();
-            let buyer_pk = G1Element::from_byte_array(
-                &Hex::decode(&args.buyer_pk).unwrap().try_into().unwrap(),
-            )
-            .unwrap();
-            let msk = G1Element::from_byte_array(
-                &Hex::decode(&args.master_sk).unwrap().try_into().unwrap(),
-            )
-            .unwrap();
-            let seller_enc_sk = Scalar::from_byte_array(
-                &Hex::decode(&args.seller_enc_sk)
-                    .unwrap()
-                    .try_into()
-                    .unwrap(),
-            )
-            .unwrap();
-            let seller_enc_pk = G1Element::generator() * seller_enc_sk;
-            // first generate the newly encrypted master key under the buyer pk.
-            let gen = <G1Element as GroupElement>::generator();
-            let mut rng = rand::thread_rng();
-            let encryption_randomness = <G1Element as GroupElement>::ScalarType::rand(&mut rng);
-            let new_enc_msk = ElGamalEncryption {
-                ephemeral: gen * encryption_randomness,
-                ciphertext: buyer_pk * encryption_randomness + msk,
+            let prev_enc_msk: ElGamalEncryption = read_bcs(&args.prev_enc_msk, encoding)?;
+            let buyer_pk = read_g1_element(&args.buyer_pk, encoding)?;
+            let seller_enc_sk = scalar_from_bytes(secret(
+                args.seller_enc_sk.as_deref(),
+                &args.key,
+                KeyKind::Encryption,
+                encoding,
+            )?)?;
+            let msk = match &args.ciphertext_path {
+                None => g1_element_from_bytes(secret(
+                    args.master_sk.as_deref(),
+                    &args.key,
+                    KeyKind::Master,
+                    encoding,
+                )?)?,
+                // Resale: recover the master key as ciphertext - ephemeral * seller_sk, and
+                // refuse to continue unless it decrypts the listing's ciphertext.
+                Some(ciphertext_path) => {
+                    let ciphertext = read_ciphertext(&mut input(ciphertext_path)?, encoding)?;
+                    let obfuscated_image = load_image(&args.obfuscated_in)?;
+                    recover_master_key(
+                        &prev_enc_msk,
+                        &seller_enc_sk,
+                        &ciphertext,
+                        &obfuscated_image.data,
+                    )?
+                }
             };
-            let new_enc_sk = Hex::encode(bcs::to_bytes(&new_enc_msk).unwrap());
-            println!("Serialized newly encrypted master key (under buyer pk):");
-            println!("{:?}", new_enc_sk);
-
-            // then generate a proof that new_enc_msk and prev_enc_msk are equivalent wrt msk.
-            let alpha: Scalar = Scalar::rand(&mut rng);
-            let beta: Scalar = Scalar::rand(&mut rng);
-
-            let u1 = gen * alpha;
-            let u2 = gen * beta;
-            let v = prev_enc_msk.ephemeral * alpha - buyer_pk * beta;
-
-            let c = fiat_shamir_challenge(
-                &seller_enc_pk,
-                &buyer_pk,
+            // generate the newly encrypted master key under the buyer pk, and a proof that
+            // new_enc_msk and prev_enc_msk are equivalent wrt msk.
+            let (new_enc_msk, proof) = elgamal::transfer(
+                &msk,
                 &prev_enc_msk,
-                &new_enc_msk,
-                u1,
-                u2,
-                v,
+                &seller_enc_sk,
+                &buyer_pk,
+                &mut rand::thread_rng(),
+            );
+            let new_enc_sk = encoding.encode_text(&bcs::to_bytes(&new_enc_msk).unwrap());
+            output.print(
+                "encrypted_msk",
+                &new_enc_sk,
+                format!(
+                    "Serialized newly encrypted master key (under buyer pk):\n{:?}",
+                    new_enc_sk
+                ),
             );
 
-            let proof = EqualityProof {
-                s1: seller_enc_sk * c + alpha,
-                s2: encryption_randomness * c + beta,
-                u1,
-                u2,
-                v,
-            };
-
-            let proof = Hex::encode(bcs::to_bytes(&proof).unwrap());
-            println!("Serialized equality proof:");
-            println!("{:?}", proof);
+            let proof = encoding.encode_text(&bcs::to_bytes(&proof).unwrap());
+            output.print(
+                "proof",
+                &proof,
+                format!("Serialized equality proof:\n{:?}", proof),
+            );
 
             Ok(())
         }
         Command::Decrypt(args) => {
-            let enc_msk: ElGamalEncryption =
-                bcs::from_bytes(&Hex::decode(&args.enc_master_sk).unwrap()).unwrap();
-            let ciphertext: FullCipherText = bcs::from_bytes(
-                &Hex::decode(&std::fs::read_to_string(&args.ciphertext_path).unwrap()).unwrap(),
-            )
-            .unwrap();
-            let obfuscated_image = load_image("obfuscated_nft.png");
-            let buyer_sk =
-                Scalar::from_byte_array(&Hex::decode(&args.buyer_sk).unwrap().try_into().unwrap())
-                    .unwrap();
-
-            let msk = enc_msk.ciphertext - enc_msk.ephemeral * buyer_sk;
-            println!(
-                "Recovered master sk: {:?}",
-                Hex::encode(msk.to_byte_array())
+            let enc_msk: ElGamalEncryption = read_bcs(&args.enc_master_sk, encoding)?;
+            let ciphertext = read_ciphertext(&mut input(&args.ciphertext_path)?, encoding)?;
+            let obfuscated_image = load_image(&args.obfuscated_in)?;
+            let msk = match (
+                args.via_keyserver.as_slice(),
+                &args.buyer_pk,
+                &args.joint_public,
+            ) {
+                // Each co-owner contributes enc_msk.ephemeral * share with a DLEQ proof, and
+                // enough of them are combined by Lagrange interpolation.
+                (_, _, Some(joint_public)) => {
+                    let shares = args
+                        .decryption_shares
+                        .iter()
+                        .map(read_json)
+                        .collect::<EnftResult<Vec<DecryptionShare>>>()?;
+                    dkg::combine_decryption_shares(
+                        &ThresholdPublicKey::from_json_file(joint_public)?,
+                        &enc_msk,
+                        &shares,
+                    )?
+                }
+                // The key server only returns enc_msk.ephemeral * buyer_sk, with a DLEQ proof
+                // that it used the key of the buyer's pubkey.
+                ([_, ..], Some(buyer_pk), None) => {
+                    let token = args.token.as_deref().ok_or_else(|| {
+                        EnftError::Unauthorized(
+                            "Set --token or ENFT_KEYSERVER_TOKEN to use a key server".to_string(),
+                        )
+                    })?;
+                    let buyer_pk = read_g1_element(buyer_pk, encoding)?;
+                    match (&args.threshold_public, args.via_keyserver.as_slice()) {
+                        (Some(path), urls) => ThresholdClient::new(
+                            ThresholdPublicKey::from_json_file(path)?,
+                            urls,
+                            token,
+                        )
+                        .decrypt(&enc_msk, &buyer_pk)?,
+                        (None, [url]) => {
+                            KeyServerClient::new(url, token).decrypt(&enc_msk, &buyer_pk)?
+                        }
+                        (None, _) => {
+                            return Err(EnftError::Threshold(
+                                "Give --threshold-public to use several key servers".to_string(),
+                            ))
+                        }
+                    }
+                }
+                _ => {
+                    let buyer_sk = scalar_from_bytes(secret(
+                        args.buyer_sk.as_deref(),
+                        &args.key,
+                        KeyKind::Encryption,
+                        encoding,
+                    )?)?;
+                    elgamal::decrypt(&enc_msk, &buyer_sk)
+                }
+            };
+            let master_key = encoding.encode_text(&msk.to_byte_array());
+            output.print(
+                "master_key",
+                &master_key,
+                format!("Recovered master sk: {:?}", master_key),
             );
 
-            let original = recover_image(&obfuscated_image.data, ciphertext, msk);
-            save_image("original_nft.png", &original);
-            println!("Original nft saved to original_nft.png. This is identical with capy.png");
+            let original = recover_image(&obfuscated_image.data, ciphertext, msk)?;
+            let path = output_path(args.output, args.out_dir.as_deref(), "original_nft.png")?;
+            save_image(&path, &original)?;
+            output.print(
+                "output_path",
+                path.display().to_string(),
+                format!("Original nft saved to {}.", path.display()),
+            );
+            Ok(())
+        }
+        Command::Inspect(args) => {
+            let inspection = inspect(&read_blob(&args.blob, encoding)?)?;
+            output.object(&inspection, &inspection);
+            inspection.check()
+        }
+        Command::CallArgs { command } => {
+            let (function, args, format) = match command {
+                CallArgsCommand::New {
+                    name,
+                    image_url,
+                    ciphertext_url,
+                    enc_master_sk,
+                    format,
+                } => {
+                    let enc: ElGamalEncryption = read_bcs(&enc_master_sk, encoding)?;
+                    let args = NewArgs::new(&name, &image_url, &ciphertext_url, &enc);
+                    (NEW_FUNCTION, args.pure_args(), format)
+                }
+                CallArgsCommand::TransferTo {
+                    to,
+                    serialized_proof,
+                    prev_enc_msk,
+                    curr_enc_msk,
+                    seller_enc_pk,
+                    buyer_enc_pk,
+                    format,
+                } => {
+                    let args = TransferToArgs::new(
+                        parse_address(&to)?,
+                        &read_g1_element(&seller_enc_pk, encoding)?,
+                        &read_g1_element(&buyer_enc_pk, encoding)?,
+                        &read_bcs(&prev_enc_msk, encoding)?,
+                        &read_bcs(&curr_enc_msk, encoding)?,
+                        &read_bcs(&serialized_proof, encoding)?,
+                    );
+                    (TRANSFER_TO_FUNCTION, args.pure_args(), format)
+                }
+            };
+            output.value("function", function);
+            output.value(
+                "arguments",
+                args.iter().map(PureArg::to_json).collect::<Vec<_>>(),
+            );
+            match format {
+                CallArgsFormat::Bcs => {
+                    for arg in &args {
+                        output.text(format!(
+                            "{} ({}): {}",
+                            arg.name,
+                            arg.value.type_name(),
+                            encoding.encode_text(&arg.value.to_bcs())
+                        ));
+                    }
+                }
+                CallArgsFormat::Json => output.text(serde_json::Value::from(
+                    args.iter()
+                        .map(|arg| arg.value.to_json())
+                        .collect::<Vec<_>>(),
+                )),
+            }
+            Ok(())
+        }
+        Command::GenVectors(args) => {
+            let vectors = vectors::generate(args.seed);
+            vectors.replay()?;
+            let path = output_path(None, args.out_dir.as_deref(), "vectors.json")?;
+            write_json(&path, &vectors)?;
+            output.print(
+                "vectors_path",
+                path.display().to_string(),
+                format!(
+                    "{} test vectors written to {}.",
+                    vectors.cases.len(),
+                    path.display()
+                ),
+            );
+            let path = output_path(None, args.out_dir.as_deref(), "vectors.move")?;
+            std::fs::write(&path, vectors.to_move_tests())?;
+            output.print(
+                "move_tests_path",
+                path.display().to_string(),
+                format!("Move tests written to {}.", path.display()),
+            );
             Ok(())
         }
         Command::Verify(args) => {
-            let proof: EqualityProof =
-                bcs::from_bytes(&Hex::decode(&args.serialized_proof).unwrap()).unwrap();
-            let seller_enc_pk = G1Element::from_byte_array(
-                &Hex::decode(&args.seller_enc_pk)
-                    .unwrap()
-                    .try_into()
-                    .unwrap(),
-            )
-            .unwrap();
-            let prev_enc_msk: ElGamalEncryption =
-                bcs::from_bytes(&Hex::decode(&args.prev_enc_msk).unwrap()).unwrap();
-            let curr_enc_msk: ElGamalEncryption =
-                bcs::from_bytes(&Hex::decode(&args.curr_enc_msk).unwrap()).unwrap();
-            let buyer_enc_pk = G1Element::from_byte_array(
-                &Hex::decode(&args.buyer_enc_pk).unwrap().try_into().unwrap(),
-            )
-            .unwrap();
-
-            let c = fiat_shamir_challenge(
+            let proof: EqualityProof = read_bcs(&args.serialized_proof, encoding)?;
+            let seller_enc_pk = read_g1_element(&args.seller_enc_pk, encoding)?;
+            let prev_enc_msk: ElGamalEncryption = read_bcs(&args.prev_enc_msk, encoding)?;
+            let curr_enc_msk: ElGamalEncryption = read_bcs(&args.curr_enc_msk, encoding)?;
+            let buyer_enc_pk = read_g1_element(&args.buyer_enc_pk, encoding)?;
+
+            elgamal::verify_equality(
                 &seller_enc_pk,
                 &buyer_enc_pk,
                 &prev_enc_msk,
                 &curr_enc_msk,
-                proof.u1,
-                proof.u2,
-                proof.v,
-            );
-            let gen = <G1Element as GroupElement>::generator();
-
-            if gen * proof.s1 != seller_enc_pk * c + proof.u1 {
-                panic!("Invalid Schnorr proof for s1");
-            }
-
-            if gen * proof.s2 != curr_enc_msk.ephemeral * c + proof.u2 {
-                panic!("Invalid Schnorr proof for s1");
-            }
-
-            if prev_enc_msk.ephemeral * proof.s1 - buyer_enc_pk * proof.s2
-                != (prev_enc_msk.ciphertext - curr_enc_msk.ciphertext) * c + proof.v
-            {
-                panic!("Invalid Schnorr proof for v");
-            }
-            println!("Proof verified.");
+                &proof,
+            )?;
+            output.print("verified", true, "Proof verified.");
             Ok(())
         }
         Command::GenerateOrDeriveEncryptionKey(args) => {
@@ -391,29 +1092,40 @@ fn execute(cmd: Command) -> Result<(), std::io::Error> {
                 .derivation_path
                 .unwrap_or(DerivationPath::from_str("m/94'/784'/0'/0'/0").unwrap());
 
+            let passphrase = if !args.passphrase {
+                String::new()
+            } else if let Ok(passphrase) = std::env::var(BIP39_PASSPHRASE_ENV) {
+                passphrase
+            } else {
+                rpassword::prompt_password("BIP39 passphrase: ")?
+            };
+
             let private_key = if let Some(mnemonics) = args.mnemonics {
-                let mnemonics = Mnemonic::from_phrase(&mnemonics, Language::English).unwrap();
-                derive_key(mnemonics, derivation_path)
+                let mnemonics = Mnemonic::from_phrase(&mnemonics, args.language.into())
+                    .map_err(|e| EnftError::InvalidMnemonic(e.to_string()))?;
+                derive_key(mnemonics, &passphrase, derivation_path, args.scheme)
             } else {
-                let mnemonics = Mnemonic::new(MnemonicType::Words12, Language::English);
-                println!("Generated mnemonics: {:?}", mnemonics.phrase());
-                derive_key(mnemonics, derivation_path)
+                let mnemonics = Mnemonic::new(args.words, args.language.into());
+                output.print(
+                    "mnemonics",
+                    mnemonics.phrase(),
+                    format!("Generated mnemonics: {:?}", mnemonics.phrase()),
+                );
+                derive_key(mnemonics, &passphrase, derivation_path, args.scheme)
             };
 
             let gen = G1Element::generator();
             let public_key = gen * private_key;
-            println!(
-                "Private encryption key: {:?}",
-                Hex::encode(private_key.to_byte_array())
-            );
-            println!(
-                "Public encryption key: {:?}",
-                Hex::encode(public_key.to_byte_array())
-            );
+            print_key_pair(output, encoding, &private_key, &public_key);
             Ok(())
         }
         Command::DeriveEncryptionKey(args) => {
-            let master_key = Hex::decode(&args.master_key).unwrap();
+            let master_key = secret(
+                args.master_key.as_deref(),
+                &args.key,
+                KeyKind::Derivation,
+                encoding,
+            )?;
             let private_key = derive_private_key(
                 &master_key,
                 &args.app_id.into_bytes(),
@@ -421,40 +1133,317 @@ fn execute(cmd: Command) -> Result<(), std::io::Error> {
             );
             let gen = G1Element::generator();
             let public_key = gen * private_key;
-            println!(
-                "Private encryption key: {:?}",
-                Hex::encode(private_key.to_byte_array())
-            );
-            println!(
-                "Public encryption key: {:?}",
-                Hex::encode(public_key.to_byte_array())
+            print_key_pair(output, encoding, &private_key, &public_key);
+            Ok(())
+        }
+        Command::DealKeyShares(args) => {
+            let (shares, public_key) = deal(args.threshold, args.shares, &mut rand::thread_rng())?;
+            let mut share_paths = vec![];
+            for share in &shares {
+                let path = output_path(
+                    None,
+                    args.out_dir.as_deref(),
+                    &format!("share-{}.json", share.index),
+                )?;
+                write_secret_json(&path, share)?;
+                output.text(format!(
+                    "Key share {} written to {}.",
+                    share.index,
+                    path.display()
+                ));
+                share_paths.push(path.display().to_string());
+            }
+            output.value("share_paths", share_paths);
+            let path = output_path(None, args.out_dir.as_deref(), "public.json")?;
+            write_json(&path, &public_key)?;
+            output.print(
+                "public_path",
+                path.display().to_string(),
+                format!(
+                    "Threshold public key ({} of {}) written to {}.",
+                    args.threshold,
+                    args.shares,
+                    path.display()
+                ),
             );
             Ok(())
         }
+        Command::Dkg { command } => {
+            match command {
+                DkgCommand::Deal {
+                    index,
+                    threshold,
+                    parties,
+                    out_dir,
+                } => {
+                    let (commitment, shares) =
+                        dkg::deal(index, threshold, parties, &mut rand::thread_rng())?;
+                    let path = output_path(
+                        None,
+                        out_dir.as_deref(),
+                        &format!("commitment-{}.json", index),
+                    )?;
+                    write_json(&path, &commitment)?;
+                    output.print(
+                        "commitment_path",
+                        path.display().to_string(),
+                        format!("Commitment written to {}.", path.display()),
+                    );
+                    let mut share_paths = vec![];
+                    for share in &shares {
+                        let path = output_path(
+                            None,
+                            out_dir.as_deref(),
+                            &format!("share-{}-for-{}.json", index, share.recipient),
+                        )?;
+                        write_secret_json(&path, share)?;
+                        output.text(format!(
+                            "Share for party {} written to {}.",
+                            share.recipient,
+                            path.display()
+                        ));
+                        share_paths.push(path.display().to_string());
+                    }
+                    output.value("share_paths", share_paths);
+                }
+                DkgCommand::Finalize {
+                    index,
+                    commitments,
+                    shares,
+                    out_dir,
+                } => {
+                    let commitments = commitments
+                        .iter()
+                        .map(read_json)
+                        .collect::<EnftResult<Vec<Commitment>>>()?;
+                    let shares = shares
+                        .iter()
+                        .map(read_json)
+                        .collect::<EnftResult<Vec<DealtShare>>>()?;
+                    let (key_share, public_key) = dkg::finalize(index, &commitments, &shares)?;
+                    let path = output_path(
+                        None,
+                        out_dir.as_deref(),
+                        &format!("key-share-{}.json", index),
+                    )?;
+                    write_secret_json(&path, &key_share)?;
+                    output.print(
+                        "key_share_path",
+                        path.display().to_string(),
+                        format!("Key share written to {}.", path.display()),
+                    );
+                    let path = output_path(None, out_dir.as_deref(), "public.json")?;
+                    write_json(&path, &public_key)?;
+                    output.print(
+                        "public_path",
+                        path.display().to_string(),
+                        format!("Public key written to {}.", path.display()),
+                    );
+                    let joint_pk = encoding.encode_text(&public_key.master_pk.to_byte_array());
+                    output.print(
+                        "joint_pk",
+                        &joint_pk,
+                        format!("Joint encryption pk: {:?}", joint_pk),
+                    );
+                }
+                DkgCommand::PartialDecrypt {
+                    key_share,
+                    enc_master_sk,
+                    output: share_out,
+                    out_dir,
+                } => {
+                    let key_share: KeyShare = read_json(&key_share)?;
+                    let enc_msk: ElGamalEncryption = read_bcs(&enc_master_sk, encoding)?;
+                    let share =
+                        dkg::decryption_share(&key_share, &enc_msk, &mut rand::thread_rng());
+                    let path = output_path(
+                        share_out,
+                        out_dir.as_deref(),
+                        &format!("decryption-share-{}.json", key_share.index),
+                    )?;
+                    write_json(&path, &share)?;
+                    output.print(
+                        "decryption_share_path",
+                        path.display().to_string(),
+                        format!("Decryption share written to {}.", path.display()),
+                    );
+                }
+            }
+            Ok(())
+        }
+        Command::Keys(args) => {
+            let path = args.keystore.unwrap_or_else(Keystore::default_path);
+            let mut keystore = Keystore::load(&path)?;
+            match args.command {
+                KeysCommand::Add {
+                    name,
+                    kind,
+                    generate,
+                } => {
+                    let secret = if generate {
+                        kind.generate(&mut rand::thread_rng())
+                    } else {
+                        encoding.decode_text(&rpassword::prompt_password(format!(
+                            "Secret key ({}): ",
+                            encoding
+                        ))?)?
+                    };
+                    let password = prompt_password(&format!("Password for key '{}': ", name))?;
+                    if std::env::var_os(PASSWORD_ENV).is_none()
+                        && prompt_password("Repeat password: ")? != password
+                    {
+                        return Err(EnftError::Keystore("Passwords do not match".to_string()));
+                    }
+                    keystore.add(&name, kind, &secret, &password, &mut rand::thread_rng())?;
+                    keystore.save(&path)?;
+                    output.value("kind", kind);
+                    output.value("keystore", path.display().to_string());
+                    output.print(
+                        "name",
+                        &name,
+                        format!("Added {} key '{}' to {}.", kind, name, path.display()),
+                    );
+                }
+                KeysCommand::List => {
+                    let mut keys = vec![];
+                    for (name, entry) in keystore.list() {
+                        match &entry.public_key {
+                            Some(pk) => output.text(format!("{}\t{}\t{}", name, entry.kind, pk)),
+                            None => output.text(format!("{}\t{}", name, entry.kind)),
+                        }
+                        keys.push(serde_json::json!({
+                            "name": name,
+                            "kind": entry.kind,
+                            "public_key": entry.public_key,
+                        }));
+                    }
+                    output.value("keys", keys);
+                }
+                KeysCommand::Export { name } => {
+                    let kind = keystore
+                        .list()
+                        .find(|(n, _)| **n == name)
+                        .map(|(_, entry)| entry.kind)
+                        .ok_or_else(|| EnftError::Keystore(format!("No key named '{}'", name)))?;
+                    let password = prompt_password(&format!("Password for key '{}': ", name))?;
+                    let secret = encoding.encode_text(&keystore.get(&name, kind, &password)?);
+                    output.print("secret", &secret, &secret);
+                }
+                KeysCommand::Remove { name } => {
+                    keystore.remove(&name)?;
+                    keystore.save(&path)?;
+                    output.print("removed", &name, format!("Removed key '{}'.", name));
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn print_key_pair(
+    output: &mut Output,
+    encoding: BlobEncoding,
+    private_key: &Scalar,
+    public_key: &G1Element,
+) {
+    let private_key = encoding.encode_text(&private_key.to_byte_array());
+    let public_key = encoding.encode_text(&public_key.to_byte_array());
+    output.print(
+        "private_key",
+        &private_key,
+        format!("Private encryption key: {:?}", private_key),
+    );
+    output.print(
+        "public_key",
+        &public_key,
+        format!("Public encryption key: {:?}", public_key),
+    );
+}
+
+/// The environment variable with the keystore password, for non-interactive use.
+const PASSWORD_ENV: &str = "ENFT_KEYSTORE_PASSWORD";
+
+/// The environment variable with the BIP39 passphrase, for non-interactive use.
+const BIP39_PASSPHRASE_ENV: &str = "ENFT_BIP39_PASSPHRASE";
+
+/// Read a password from `ENFT_KEYSTORE_PASSWORD`, or prompt for it without echo.
+fn prompt_password(prompt: &str) -> EnftResult<String> {
+    match std::env::var(PASSWORD_ENV) {
+        Ok(password) => Ok(password),
+        Err(_) => Ok(rpassword::prompt_password(prompt)?),
+    }
+}
+
+/// Take a secret given in hex on the command line, or else from the keystore entry
+/// of the given kind among the `--key` names.
+fn secret(
+    arg: Option<&str>,
+    key: &KeyArgs,
+    kind: KeyKind,
+    encoding: BlobEncoding,
+) -> EnftResult<Vec<u8>> {
+    if let Some(arg) = arg {
+        return read_blob(arg, encoding);
+    }
+    let keystore = Keystore::load(key.keystore.clone().unwrap_or_else(Keystore::default_path))?;
+    let name = key
+        .keys
+        .iter()
+        .find(|name| {
+            keystore
+                .list()
+                .any(|(n, entry)| n == *name && entry.kind == kind)
+        })
+        .ok_or_else(|| EnftError::Keystore(format!("No {} key given with --key", kind)))?;
+    keystore.get(
+        name,
+        kind,
+        &prompt_password(&format!("Password for key '{}': ", name))?,
+    )
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: impl AsRef<Path>) -> EnftResult<T> {
+    serde_json::from_slice(&std::fs::read(path)?).map_err(|e| EnftError::Io(e.into()))
+}
+
+fn write_json<T: serde::Serialize>(path: &Path, value: &T) -> EnftResult<()> {
+    serde_json::to_writer_pretty(File::create(path)?, value).map_err(|e| EnftError::Io(e.into()))
+}
+
+/// Write a secret to a new file that is readable by the owner only.
+fn write_secret_json<T: serde::Serialize>(path: &Path, value: &T) -> EnftResult<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    serde_json::to_writer_pretty(options.open(path)?, value).map_err(|e| EnftError::Io(e.into()))
+}
+
+/// Open an input file, or stdin for `-`.
+fn input(path: impl AsRef<Path>) -> EnftResult<Box<dyn Read>> {
+    let path = path.as_ref();
+    if path == Path::new("-") {
+        return Ok(Box::new(std::io::stdin()));
     }
+    Ok(Box::new(File::open(path)?))
 }
 
-fn fiat_shamir_challenge(
-    pk1: &G1Element,
-    pk2: &G1Element,
-    enc1: &ElGamalEncryption,
-    enc2: &ElGamalEncryption,
-    a1: G1Element,
-    a2: G1Element,
-    a3: G1Element,
-) -> Scalar {
-    let mut fiat_shamir_msg = Blake2b256::new();
-    fiat_shamir_msg.update(pk1.to_byte_array());
-    fiat_shamir_msg.update(pk2.to_byte_array());
-    fiat_shamir_msg.update(enc1.ephemeral.to_byte_array());
-    fiat_shamir_msg.update(enc1.ciphertext.to_byte_array());
-    fiat_shamir_msg.update(enc2.ephemeral.to_byte_array());
-    fiat_shamir_msg.update(enc2.ciphertext.to_byte_array());
-    fiat_shamir_msg.update(a1.to_byte_array());
-    fiat_shamir_msg.update(a2.to_byte_array());
-    fiat_shamir_msg.update(a3.to_byte_array());
-
-    let mut digest = fiat_shamir_msg.finalize().digest;
-    digest[31] = 0;
-    Scalar::from_byte_array(&digest).unwrap()
+/// Resolve where to write an output file. An explicit path wins, otherwise the
+/// default file name is used inside `out_dir` (created if missing) or the
+/// current directory.
+fn output_path(
+    explicit: Option<PathBuf>,
+    out_dir: Option<&Path>,
+    default_name: &str,
+) -> EnftResult<PathBuf> {
+    if let Some(path) = explicit {
+        return Ok(path);
+    }
+    match out_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            Ok(dir.join(default_name))
+        }
+        None => Ok(PathBuf::from(default_name)),
+    }
 }
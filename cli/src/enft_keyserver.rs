@@ -0,0 +1,56 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use clap::Parser;
+use enft_cli::error::EnftResult;
+use enft_cli::keyserver::{KeyServer, TrustedIssuer};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "enft-keyserver")]
+#[command(about = "A key server deriving encryption keys for users authenticated with JWTs", long_about = None)]
+struct Args {
+    /// A file with the hex encoded master key of the server.
+    #[clap(long, required_unless_present = "share_file")]
+    master_key_file: Option<PathBuf>,
+
+    /// A key share written by `enft-cli deal-key-shares`, to run as one of the
+    /// servers of a threshold key server instead.
+    #[clap(long, conflicts_with = "master_key_file")]
+    share_file: Option<PathBuf>,
+
+    /// A trusted token issuer as `<iss>=<jwks file>`, with the keys that its tokens
+    /// are signed with. Can be repeated.
+    #[clap(long = "issuer", required = true)]
+    issuers: Vec<String>,
+
+    /// The address to listen on.
+    #[clap(long, default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run(Args::parse()).await {
+        println!("Error: {}", e);
+        std::process::exit(e.exit_code());
+    }
+}
+
+async fn run(args: Args) -> EnftResult<()> {
+    let issuers = args
+        .issuers
+        .iter()
+        .map(|issuer| TrustedIssuer::from_arg(issuer))
+        .collect::<EnftResult<Vec<_>>>()?;
+    let server = match (&args.master_key_file, &args.share_file) {
+        (Some(master_key_file), _) => KeyServer::from_files(master_key_file, issuers)?,
+        (None, Some(share_file)) => KeyServer::from_share_files(share_file, issuers)?,
+        (None, None) => unreachable!("clap requires one of the key files"),
+    };
+    let listener = tokio::net::TcpListener::bind(args.listen).await?;
+    println!("Key server listening on {}.", args.listen);
+    axum::serve(listener, server.router()).await?;
+    Ok(())
+}
@@ -0,0 +1,101 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::elgamal::InvalidProof;
+use thiserror::Error;
+
+pub type EnftResult<T> = Result<T, EnftError>;
+
+/// Errors returned by the library and the CLI. Each variant maps to its own
+/// process exit code, see [EnftError::exit_code].
+#[derive(Debug, Error)]
+pub enum EnftError {
+    #[error("Proof rejected: {0}")]
+    ProofRejected(#[from] InvalidProof),
+
+    #[error("Invalid hex encoding")]
+    InvalidHex,
+
+    #[error("Invalid base64 encoding")]
+    InvalidBase64,
+
+    #[error("Invalid length: expected {expected} bytes, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+
+    #[error("Invalid group element")]
+    InvalidGroupElement,
+
+    #[error("Invalid scalar")]
+    InvalidScalar,
+
+    #[error("BCS deserialization failed: {0}")]
+    Bcs(#[from] bcs::Error),
+
+    #[error("AES-GCM authentication failed")]
+    AuthenticationFailed,
+
+    #[error("Ciphertext does not match the obfuscated image")]
+    ImageMismatch,
+
+    #[error("Image error: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+
+    #[error("Invalid region: {0}")]
+    InvalidRegion(String),
+
+    #[error("Unsupported ciphertext format version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("The secret key does not decrypt the encrypted master key")]
+    WrongKey,
+
+    #[error("Keystore error: {0}")]
+    Keystore(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Key server request failed: {0}")]
+    KeyServer(String),
+
+    #[error("Threshold error: {0}")]
+    Threshold(String),
+
+    #[error("Test vector error: {0}")]
+    TestVector(String),
+}
+
+impl EnftError {
+    /// The process exit code for this error. `1` is reserved for a rejected
+    /// proof so that scripts can tell it apart from malformed input.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            EnftError::ProofRejected(_) => 1,
+            EnftError::InvalidHex => 10,
+            EnftError::InvalidLength { .. } => 11,
+            EnftError::InvalidGroupElement => 12,
+            EnftError::InvalidScalar => 13,
+            EnftError::Bcs(_) => 14,
+            EnftError::AuthenticationFailed => 15,
+            EnftError::ImageMismatch => 16,
+            EnftError::Image(_) => 17,
+            EnftError::Io(_) => 18,
+            EnftError::InvalidMnemonic(_) => 19,
+            EnftError::InvalidRegion(_) => 20,
+            EnftError::UnsupportedVersion(_) => 21,
+            EnftError::WrongKey => 22,
+            EnftError::Keystore(_) => 23,
+            EnftError::Unauthorized(_) => 24,
+            EnftError::KeyServer(_) => 25,
+            EnftError::Threshold(_) => 26,
+            EnftError::InvalidBase64 => 27,
+            EnftError::TestVector(_) => 28,
+        }
+    }
+}
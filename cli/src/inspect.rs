@@ -0,0 +1,459 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::ciphertext::{CipherSuite, FullCipherText};
+use crate::error::{EnftError, EnftResult};
+use crate::region::Region;
+use crate::utils::{g1_element_from_bytes, scalar_from_bytes};
+use fastcrypto::encoding::{Encoding, Hex};
+use fastcrypto::groups::bls12381::G1Element;
+use fastcrypto::groups::GroupElement;
+use serde::Serialize;
+use std::fmt;
+
+/// The length of the AES-GCM tag appended to the encrypted pixel values.
+pub const GCM_TAG_LENGTH: usize = 16;
+
+const G1_LENGTH: usize = 48;
+const SCALAR_LENGTH: usize = 32;
+
+/// The BCS length of an ElGamalEncryption: two G1 elements.
+const ENCRYPTION_LENGTH: usize = 2 * G1_LENGTH;
+
+/// The BCS length of an EqualityProof: two scalars and three G1 elements.
+const PROOF_LENGTH: usize = 2 * SCALAR_LENGTH + 3 * G1_LENGTH;
+
+/// Whether an encoded group element or scalar is usable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ElementStatus {
+    Valid,
+    /// The identity of G1, which no honest key, encryption or proof contains.
+    Identity,
+    /// Not a canonical encoding of a G1 element in the prime order subgroup, or of a scalar.
+    Invalid,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Element {
+    pub hex: String,
+    pub status: ElementStatus,
+}
+
+impl Element {
+    fn g1(bytes: &[u8]) -> Self {
+        let status = match g1_element_from_bytes(bytes.to_vec()) {
+            Ok(point) if point == G1Element::zero() => ElementStatus::Identity,
+            Ok(_) => ElementStatus::Valid,
+            Err(_) => ElementStatus::Invalid,
+        };
+        Element {
+            hex: Hex::encode(bytes),
+            status,
+        }
+    }
+
+    fn scalar(bytes: &[u8]) -> Self {
+        let status = match scalar_from_bytes(bytes.to_vec()) {
+            Ok(_) => ElementStatus::Valid,
+            Err(_) => ElementStatus::Invalid,
+        };
+        Element {
+            hex: Hex::encode(bytes),
+            status,
+        }
+    }
+}
+
+/// A decoded eNFT artifact with the results of its checks.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Inspection {
+    ElGamalEncryption {
+        ephemeral: Element,
+        ciphertext: Element,
+    },
+    EqualityProof {
+        s1: Element,
+        s2: Element,
+        u1: Element,
+        u2: Element,
+        v: Element,
+    },
+    FullCipherText {
+        version: u8,
+        /// The cipher suite and its parameters, for v1 ciphertexts.
+        cipher_suite: Option<String>,
+        /// The image (width, height), for v1 ciphertexts.
+        dimensions: Option<(u32, u32)>,
+        region: String,
+        /// The (row, column) indices of the obfuscated chunks, if chunks were sampled.
+        chunks: Option<Vec<(usize, usize)>>,
+        iv: String,
+        data_length: usize,
+        /// The data length implied by the region. For v0 ciphertexts the image size
+        /// is not recorded, so this assumes that no chunk is cut off at the border.
+        expected_data_length: Option<usize>,
+        /// Why the region does not fit the image, if it does not.
+        region_error: Option<String>,
+    },
+}
+
+/// Decode an ElGamalEncryption, an EqualityProof or a FullCipherText from its BCS
+/// bytes. Encryptions and proofs are told apart by their fixed lengths, anything
+/// else is read as a ciphertext.
+pub fn inspect(bytes: &[u8]) -> EnftResult<Inspection> {
+    match bytes.len() {
+        ENCRYPTION_LENGTH => Ok(Inspection::ElGamalEncryption {
+            ephemeral: Element::g1(&bytes[..G1_LENGTH]),
+            ciphertext: Element::g1(&bytes[G1_LENGTH..]),
+        }),
+        PROOF_LENGTH => {
+            let points = &bytes[2 * SCALAR_LENGTH..];
+            Ok(Inspection::EqualityProof {
+                s1: Element::scalar(&bytes[..SCALAR_LENGTH]),
+                s2: Element::scalar(&bytes[SCALAR_LENGTH..2 * SCALAR_LENGTH]),
+                u1: Element::g1(&points[..G1_LENGTH]),
+                u2: Element::g1(&points[G1_LENGTH..2 * G1_LENGTH]),
+                v: Element::g1(&points[2 * G1_LENGTH..]),
+            })
+        }
+        _ => Ok(inspect_ciphertext(&FullCipherText::from_bytes(bytes)?)),
+    }
+}
+
+fn inspect_ciphertext(ciphertext: &FullCipherText) -> Inspection {
+    let region = ciphertext.region();
+    let too_large = || EnftError::InvalidRegion("The region is too large".to_string());
+    let (cipher_suite, dimensions, expected_pixels) = match ciphertext {
+        FullCipherText::V0(_) => {
+            let pixels = match &region {
+                Region::Chunks {
+                    geometry, indices, ..
+                } => indices
+                    .len()
+                    .checked_mul(geometry.width as usize * geometry.height as usize)
+                    .ok_or_else(too_large),
+                _ => unreachable!("v0 ciphertexts always have chunks"),
+            };
+            (None, None, pixels)
+        }
+        FullCipherText::V1(v1) => {
+            let header = &v1.header;
+            let cipher_suite = match &header.cipher_suite {
+                CipherSuite::Aes256GcmLegacyKey => "AES-256-GCM with the legacy key".to_string(),
+                CipherSuite::Aes256GcmHkdfSha256 { label } => {
+                    format!("AES-256-GCM with an HKDF-SHA256 key ({})", label)
+                }
+            };
+            // The dimensions are untrusted, so the pixels are counted, not listed.
+            let pixels = region
+                .pixel_count(header.width as usize, header.height as usize)
+                .map_err(|e| match e {
+                    EnftError::ImageMismatch => EnftError::InvalidRegion(format!(
+                        "The region does not fit the {}x{} image",
+                        header.width, header.height
+                    )),
+                    e => e,
+                });
+            (
+                Some(cipher_suite),
+                Some((header.width, header.height)),
+                pixels,
+            )
+        }
+    };
+    let (expected_data_length, region_error) = match expected_pixels.and_then(|pixels| {
+        pixels
+            .checked_mul(4)
+            .and_then(|bytes| bytes.checked_add(GCM_TAG_LENGTH))
+            .ok_or_else(too_large)
+    }) {
+        Ok(length) => (Some(length), None),
+        Err(EnftError::InvalidRegion(error)) => (None, Some(error)),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let (summary, chunks) = match &region {
+        Region::Chunks {
+            geometry,
+            indices,
+            strategy,
+        } => (
+            format!(
+                "{} chunks of {}x{} ({})",
+                indices.len(),
+                geometry.width,
+                geometry.height,
+                strategy
+            ),
+            Some(indices.clone()),
+        ),
+        Region::Mask(runs) => (
+            format!(
+                "mask with {} pixels in {} runs",
+                runs.iter()
+                    .fold(0u64, |pixels, (_, len)| pixels.saturating_add(*len)),
+                runs.len()
+            ),
+            None,
+        ),
+        Region::Shapes(spec) => (
+            format!(
+                "{} rectangles and {} polygons",
+                spec.rectangles.len(),
+                spec.polygons.len()
+            ),
+            None,
+        ),
+    };
+    Inspection::FullCipherText {
+        version: ciphertext.version(),
+        cipher_suite,
+        dimensions,
+        region: summary,
+        chunks,
+        iv: Hex::encode(ciphertext.iv().as_ref()),
+        data_length: ciphertext.data().len(),
+        expected_data_length,
+        region_error,
+    }
+}
+
+impl Inspection {
+    /// Fail if a point is not a valid non-identity G1 element, a scalar is invalid, or
+    /// the region does not fit the image or the ciphertext size does not match it.
+    pub fn check(&self) -> EnftResult<()> {
+        let elements = match self {
+            Inspection::ElGamalEncryption {
+                ephemeral,
+                ciphertext,
+            } => vec![(ephemeral, false), (ciphertext, false)],
+            Inspection::FullCipherText {
+                region_error: Some(error),
+                ..
+            } => return Err(EnftError::InvalidRegion(error.clone())),
+            Inspection::EqualityProof { s1, s2, u1, u2, v } => {
+                vec![(s1, true), (s2, true), (u1, false), (u2, false), (v, false)]
+            }
+            Inspection::FullCipherText {
+                data_length,
+                expected_data_length: Some(expected),
+                ..
+            } if data_length != expected => {
+                return Err(EnftError::InvalidLength {
+                    expected: *expected,
+                    actual: *data_length,
+                })
+            }
+            Inspection::FullCipherText { .. } => vec![],
+        };
+        for (element, is_scalar) in elements {
+            match (element.status, is_scalar) {
+                (ElementStatus::Valid, _) => {}
+                (_, true) => return Err(EnftError::InvalidScalar),
+                (_, false) => return Err(EnftError::InvalidGroupElement),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self.status {
+            ElementStatus::Valid => "valid",
+            ElementStatus::Identity => "INVALID: identity",
+            ElementStatus::Invalid => "INVALID",
+        };
+        write!(f, "{} ({})", self.hex, status)
+    }
+}
+
+impl fmt::Display for Inspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Inspection::ElGamalEncryption {
+                ephemeral,
+                ciphertext,
+            } => {
+                writeln!(f, "ElGamalEncryption")?;
+                writeln!(f, "  ephemeral:  {}", ephemeral)?;
+                write!(f, "  ciphertext: {}", ciphertext)
+            }
+            Inspection::EqualityProof { s1, s2, u1, u2, v } => {
+                writeln!(f, "EqualityProof")?;
+                writeln!(f, "  s1: {}", s1)?;
+                writeln!(f, "  s2: {}", s2)?;
+                writeln!(f, "  u1: {}", u1)?;
+                writeln!(f, "  u2: {}", u2)?;
+                write!(f, "  v:  {}", v)
+            }
+            Inspection::FullCipherText {
+                version,
+                cipher_suite,
+                dimensions,
+                region,
+                chunks,
+                iv,
+                data_length,
+                expected_data_length,
+                region_error,
+            } => {
+                writeln!(f, "FullCipherText v{}", version)?;
+                if let Some(cipher_suite) = cipher_suite {
+                    writeln!(f, "  cipher suite: {}", cipher_suite)?;
+                }
+                if let Some((width, height)) = dimensions {
+                    writeln!(f, "  image: {}x{}", width, height)?;
+                }
+                match region_error {
+                    Some(error) => writeln!(f, "  region: {} (INVALID: {})", region, error)?,
+                    None => writeln!(f, "  region: {}", region)?,
+                }
+                if let Some(chunks) = chunks {
+                    writeln!(f, "  chunks: {:?}", chunks)?;
+                }
+                writeln!(f, "  iv: {}", iv)?;
+                match expected_data_length {
+                    Some(expected) if expected == data_length => {
+                        write!(
+                            f,
+                            "  data length: {} bytes (matches the region)",
+                            data_length
+                        )
+                    }
+                    Some(expected) => write!(
+                        f,
+                        "  data length: {} bytes (MISMATCH: the region needs {})",
+                        data_length, expected
+                    ),
+                    None => write!(f, "  data length: {} bytes", data_length),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ciphertext::{CipherTextHeader, CipherTextV0, CipherTextV1, FORMAT_VERSION, MAGIC};
+    use crate::elgamal;
+    use crate::utils::msk_to_cipher;
+    use fastcrypto::aes::{Cipher, InitializationVector};
+    use fastcrypto::groups::bls12381::Scalar;
+    use fastcrypto::groups::Scalar as _;
+    use fastcrypto::serde_helpers::ToFromByteArray;
+    use fastcrypto::traits::Generate;
+    use typenum::U12;
+
+    #[test]
+    fn test_inspect() {
+        let mut rng = rand::thread_rng();
+        let gen = G1Element::generator();
+        let (sk1, sk2) = (Scalar::rand(&mut rng), Scalar::rand(&mut rng));
+        let msk = gen * Scalar::rand(&mut rng);
+        let (enc1, _) = elgamal::encrypt(&msk, &(gen * sk1), &mut rng);
+        let (enc2, proof) = elgamal::transfer(&msk, &enc1, &sk1, &(gen * sk2), &mut rng);
+
+        let inspection = inspect(&bcs::to_bytes(&enc2).unwrap()).unwrap();
+        assert!(matches!(inspection, Inspection::ElGamalEncryption { .. }));
+        assert!(inspection.check().is_ok());
+        let inspection = inspect(&bcs::to_bytes(&proof).unwrap()).unwrap();
+        assert!(matches!(inspection, Inspection::EqualityProof { .. }));
+        assert!(inspection.check().is_ok());
+
+        // An encryption with the identity as ephemeral key.
+        let mut bytes = bcs::to_bytes(&enc2).unwrap();
+        bytes[..G1_LENGTH].copy_from_slice(&G1Element::zero().to_byte_array());
+        let inspection = inspect(&bytes).unwrap();
+        let Inspection::ElGamalEncryption { ephemeral, .. } = &inspection else {
+            panic!("expected an encryption");
+        };
+        assert_eq!(ephemeral.status, ElementStatus::Identity);
+        assert!(matches!(
+            inspection.check(),
+            Err(EnftError::InvalidGroupElement)
+        ));
+
+        // A point that is not on the curve.
+        bytes[..G1_LENGTH].copy_from_slice(&[0xaa; G1_LENGTH]);
+        assert!(inspect(&bytes).unwrap().check().is_err());
+    }
+
+    #[test]
+    fn test_inspect_ciphertext() {
+        let mut rng = rand::thread_rng();
+        let master_key = G1Element::generator() * Scalar::rand(&mut rng);
+        let iv = InitializationVector::<U12>::generate(&mut rng);
+        let mut v0 = CipherTextV0 {
+            pixels: vec![(0, 1), (2, 3)],
+            data: msk_to_cipher(&master_key).encrypt(&iv, &[7; 2 * 4 * 100 * 100]),
+            iv,
+        };
+        let inspection = inspect(&bcs::to_bytes(&v0).unwrap()).unwrap();
+        let Inspection::FullCipherText { chunks, .. } = &inspection else {
+            panic!("expected a ciphertext");
+        };
+        assert_eq!(chunks, &Some(vec![(0, 1), (2, 3)]));
+        assert!(inspection.check().is_ok());
+
+        v0.data.truncate(100);
+        assert!(matches!(
+            inspect(&bcs::to_bytes(&v0).unwrap()).unwrap().check(),
+            Err(EnftError::InvalidLength { actual: 100, .. })
+        ));
+    }
+
+    #[test]
+    fn test_inspect_invalid_region() {
+        let mut rng = rand::thread_rng();
+        let mut v1 = CipherTextV1 {
+            header: CipherTextHeader {
+                magic: MAGIC,
+                version: FORMAT_VERSION,
+                cipher_suite: CipherSuite::Aes256GcmLegacyKey,
+                width: 32768,
+                height: 32768,
+                region: Region::Mask(vec![(0, 1 << 30)]),
+                obfuscated_image_hash: [0; 32],
+            },
+            iv: InitializationVector::<U12>::generate(&mut rng),
+            data: vec![0; 100],
+        };
+        // A large region is counted, not listed.
+        let inspection = inspect(&bcs::to_bytes(&v1).unwrap()).unwrap();
+        let Inspection::FullCipherText {
+            expected_data_length,
+            region_error,
+            ..
+        } = &inspection
+        else {
+            panic!("expected a ciphertext");
+        };
+        assert_eq!(*expected_data_length, Some(4 * (1 << 30) + GCM_TAG_LENGTH));
+        assert_eq!(*region_error, None);
+
+        // A region outside the image is reported instead of ignored.
+        for region in [
+            Region::Mask(vec![(0, u64::MAX), (u64::MAX, 1)]),
+            Region::Mask(vec![(1 << 30, 1)]),
+        ] {
+            v1.header.region = region;
+            let inspection = inspect(&bcs::to_bytes(&v1).unwrap()).unwrap();
+            assert!(matches!(
+                inspection,
+                Inspection::FullCipherText {
+                    expected_data_length: None,
+                    region_error: Some(_),
+                    ..
+                }
+            ));
+            assert!(matches!(
+                inspection.check(),
+                Err(EnftError::InvalidRegion(_))
+            ));
+            assert!(inspection.to_string().contains("INVALID"));
+        }
+    }
+}
@@ -4,18 +4,73 @@
 use bip32::DerivationPath;
 use bip39::{Mnemonic, Seed};
 use fastcrypto::groups::bls12381::{reduce_mod_uniform_buffer, Scalar, G1_ELEMENT_BYTE_LENGTH};
-use fastcrypto::groups::Scalar as _;
+use fastcrypto::groups::{GroupElement, Scalar as _};
 use fastcrypto::hmac::{hkdf_sha3_256, HkdfIkm};
+use fastcrypto::serde_helpers::ToFromByteArray;
 use fastcrypto::traits::ToFromBytes;
+use hkdf::Hkdf;
 use rand::{rngs::StdRng, SeedableRng};
-/// Given a seed and a derivation path, derive a scalar (a BLS12381 private key).
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+/// How a private key is derived from a seed and a derivation path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DerivationScheme {
+    /// The scheme of SIP-23, see [derive_key_from_seed]. Keys depend on the
+    /// algorithm of `StdRng` and cannot be reproduced outside of Rust.
+    #[default]
+    Sip23,
+    /// EIP-2333, see [eip2333_derive_key].
+    Eip2333,
+}
+
+impl fmt::Display for DerivationScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DerivationScheme::Sip23 => write!(f, "sip23"),
+            DerivationScheme::Eip2333 => write!(f, "eip2333"),
+        }
+    }
+}
+
+impl FromStr for DerivationScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sip23" => Ok(DerivationScheme::Sip23),
+            "eip2333" => Ok(DerivationScheme::Eip2333),
+            _ => Err(format!(
+                "Unknown derivation scheme '{}', expected sip23 or eip2333",
+                s
+            )),
+        }
+    }
+}
+
+/// Given mnemonics, an optional BIP39 passphrase (empty if none) and a derivation
+/// path, derive a scalar (a BLS12381 private key) with the given scheme.
+pub fn derive_key(
+    mnemonics: Mnemonic,
+    passphrase: &str,
+    path: DerivationPath,
+    scheme: DerivationScheme,
+) -> Scalar {
+    let seed = Seed::new(&mnemonics, passphrase);
+    match scheme {
+        DerivationScheme::Sip23 => derive_key_from_seed(seed.as_bytes(), path),
+        DerivationScheme::Eip2333 => eip2333_derive_key(seed.as_bytes(), path),
+    }
+}
+
+/// Given a BIP39 seed and a derivation path, derive a scalar (a BLS12381 private key).
 /// This code is referenced from https://crates.io/crates/slip10_ed25519/0.1.3 with slight modifications.
 #[allow(non_snake_case)]
-pub fn derive_key(mnemonics: Mnemonic, path: DerivationPath) -> Scalar {
-    let seed = Seed::new(&mnemonics, "");
+pub fn derive_key_from_seed(seed: &[u8], path: DerivationPath) -> Scalar {
     let indexes: Vec<u32> = path.into_iter().map(|i| i.into()).collect::<Vec<_>>();
 
-    let mut I = hmac_sha512(b"bls12381 seed", seed.as_bytes());
+    let mut I = hmac_sha512(b"bls12381 seed", seed);
     let mut data = [0u8; 37];
 
     for i in indexes {
@@ -37,6 +92,62 @@ fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; 64] {
     hmac_sha512::HMAC::mac(data, key)
 }
 
+/// Derive a key following EIP-2333: the master key is derived from the seed, and
+/// each index of the path derives a child key. Indices are used as 32-bit numbers,
+/// so a hardened index like `0'` is `0x80000000`.
+pub fn eip2333_derive_key(seed: &[u8], path: DerivationPath) -> Scalar {
+    path.into_iter()
+        .fold(eip2333_master_key(seed), |sk, index| {
+            eip2333_child_key(&sk, index.into())
+        })
+}
+
+/// The EIP-2333 master key of a seed of at least 32 bytes.
+pub fn eip2333_master_key(seed: &[u8]) -> Scalar {
+    hkdf_mod_r(seed)
+}
+
+/// The EIP-2333 child key of a parent key at the given index.
+pub fn eip2333_child_key(parent_sk: &Scalar, index: u32) -> Scalar {
+    hkdf_mod_r(&parent_sk_to_lamport_pk(parent_sk, index))
+}
+
+/// HKDF_mod_r from EIP-2333 with an empty key_info.
+fn hkdf_mod_r(ikm: &[u8]) -> Scalar {
+    const L: usize = 48;
+    let mut salt = Sha256::digest(b"BLS-SIG-KEYGEN-SALT-");
+    loop {
+        let hkdf = Hkdf::<Sha256>::new(Some(salt.as_slice()), &[ikm, &[0u8]].concat());
+        let mut okm = [0u8; L];
+        hkdf.expand(&(L as u16).to_be_bytes(), &mut okm)
+            .expect("valid output length");
+        let sk = reduce_mod_uniform_buffer(&okm);
+        if sk != Scalar::zero() {
+            return sk;
+        }
+        salt = Sha256::digest(salt);
+    }
+}
+
+/// The compressed lamport pubkey of EIP-2333, computed from the parent key and
+/// its flipped bits.
+fn parent_sk_to_lamport_pk(parent_sk: &Scalar, index: u32) -> [u8; 32] {
+    let salt = index.to_be_bytes();
+    let ikm = parent_sk.to_byte_array();
+    let not_ikm = ikm.map(|b| !b);
+    let mut lamport_pk = Sha256::new();
+    for ikm in [ikm, not_ikm] {
+        let mut okm = [0u8; 255 * 32];
+        Hkdf::<Sha256>::new(Some(salt.as_slice()), &ikm)
+            .expand(&[], &mut okm)
+            .expect("valid output length");
+        for chunk in okm.chunks_exact(32) {
+            lamport_pk.update(Sha256::digest(chunk));
+        }
+    }
+    lamport_pk.finalize().into()
+}
+
 pub fn derive_private_key(master_key: &[u8], app_id: &[u8], user_id: &[u8]) -> Scalar {
     // First derive the bytes from master key, app_id and user_id with HKDF, outputs 48 bytes.
     let bytes = hkdf_sha3_256(
@@ -50,3 +161,147 @@ pub fn derive_private_key(master_key: &[u8], app_id: &[u8], user_id: &[u8]) -> S
     // Then reduce bytes into the subgroup of the BLS12-381 private keys.
     reduce_mod_uniform_buffer(&bytes)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bip39::Language;
+    use fastcrypto::encoding::{Encoding, Hex};
+
+    const MNEMONICS: &str =
+        "dove vault canoe aisle tiger layer tape occur arrange control raccoon guilt";
+
+    fn derive(
+        phrase: &str,
+        language: Language,
+        passphrase: &str,
+        path: &str,
+        scheme: DerivationScheme,
+    ) -> String {
+        let mnemonics = Mnemonic::from_phrase(phrase, language).unwrap();
+        let path = DerivationPath::from_str(path).unwrap();
+        Hex::encode(derive_key(mnemonics, passphrase, path, scheme).to_byte_array())
+    }
+
+    #[test]
+    fn test_derive_key_vectors() {
+        // Frozen vectors of the SIP-23 scheme, as documented in the README. These would
+        // change if StdRng did, which must never happen to existing users' keys.
+        for (phrase, language, passphrase, path, key) in [
+            (
+                MNEMONICS,
+                Language::English,
+                "",
+                "m/94'/784'/0'/0'/0",
+                "26151c5c0cb67ab2f2f37d000374a629ae1b7f35658d1bd5af4954e5c7ff8f81",
+            ),
+            (
+                MNEMONICS,
+                Language::English,
+                "",
+                "m/94'/784'/1'/0'/0",
+                "257f94f04abdc37734e8e637085f9a04eac0a2b81c5a0d39d88d1fa2db12a643",
+            ),
+            (
+                MNEMONICS,
+                Language::English,
+                "TREZOR",
+                "m/94'/784'/0'/0'/0",
+                "5adb5c36c9b6d1689c164e2e30224e15c01a3ca45fb80492411b159051f5610c",
+            ),
+            (
+                "advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic avoid negative",
+                Language::English,
+                "",
+                "m/94'/784'/0'/0'/0",
+                "12cfb992b9da573d99caaa6d20986ff310a26b888026ba75c2ea62d854f0ba25",
+            ),
+            (
+                "あたえる いよく そとづら あまど おおう あこがれる いくぶん けいけん あたえる いよく そとづら あまり",
+                Language::Japanese,
+                "",
+                "m/94'/784'/0'/0'/0",
+                "2a9b0ff618fddb0c4a2f2331cef7654f59201bbf034cec398634cc4214da3a3c",
+            ),
+        ] {
+            assert_eq!(
+                derive(phrase, language, passphrase, path, DerivationScheme::Sip23),
+                key
+            );
+        }
+
+        // EIP-2333 through derive_key, on the EIP-2334 signing key path.
+        assert_eq!(
+            derive(
+                MNEMONICS,
+                Language::English,
+                "",
+                "m/12381/3600/0/0/0",
+                DerivationScheme::Eip2333
+            ),
+            "1ccf25b5e16bacfdb9629a78a09e97b5d977ab431fbf071680a089068f38c986"
+        );
+    }
+
+    #[test]
+    fn test_eip2333_vectors() {
+        // The test vectors from EIP-2333, as (seed, master key, child index, child key).
+        for (seed, master_sk, index, child_sk) in [
+            (
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+                "0d7359d57963ab8fbbde1852dcf553fedbc31f464d80ee7d40ae683122b45070",
+                0,
+                "2d18bd6c14e6d15bf8b5085c9b74f3daae3b03cc2014770a599d8c1539e50f8e",
+            ),
+            (
+                "3141592653589793238462643383279502884197169399375105820974944592",
+                "41c9e07822b092a93fd6797396338c3ada4170cc81829fdfce6b5d34bd5e7ec7",
+                3141592653,
+                "384843fad5f3d777ea39de3e47a8f999ae91f89e42bffa993d91d9782d152a0f",
+            ),
+            (
+                "0099FF991111002299DD7744EE3355BBDD8844115566CC55663355668888CC00",
+                "3cfa341ab3910a7d00d933d8f7c4fe87c91798a0397421d6b19fd5b815132e80",
+                4294967295,
+                "40e86285582f35b28821340f6a53b448588efa575bc4d88c32ef8567b8d9479b",
+            ),
+            (
+                "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+                "2a0e28ffa5fbbe2f8e7aad4ed94f745d6bf755c51182e119bb1694fe61d3afca",
+                42,
+                "455c0dc9fccb3395825d92a60d2672d69416be1c2578a87a7a3d3ced11ebb88d",
+            ),
+        ] {
+            let master = eip2333_master_key(&Hex::decode(seed).unwrap());
+            assert_eq!(Hex::encode(master.to_byte_array()), master_sk);
+            let child = eip2333_child_key(&master, index);
+            assert_eq!(Hex::encode(child.to_byte_array()), child_sk);
+        }
+    }
+
+    #[test]
+    fn test_seed_vectors() {
+        // From the BIP39 reference test vectors, with passphrase "TREZOR".
+        for (phrase, seed) in [
+            (
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+            ),
+            (
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+                "bda85446c68413707090a52022edd26a1c9462295029f2e60cd7c4f2bbd3097170af7a4d73245cafa9c3cca8d561a7c3de6f5d4a10be8ed2a5e608d68f92fcc8",
+            ),
+        ] {
+            let mnemonics = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+            assert_eq!(Hex::encode(Seed::new(&mnemonics, "TREZOR").as_bytes()), seed);
+        }
+    }
+
+    #[test]
+    fn test_wordlists() {
+        let mnemonics = Mnemonic::new(bip39::MnemonicType::Words24, Language::Japanese);
+        assert_eq!(mnemonics.phrase().split_whitespace().count(), 24);
+        assert!(Mnemonic::from_phrase(mnemonics.phrase(), Language::Japanese).is_ok());
+        assert!(Mnemonic::from_phrase(mnemonics.phrase(), Language::English).is_err());
+    }
+}
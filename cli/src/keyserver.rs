@@ -0,0 +1,649 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::elgamal;
+use crate::elgamal::{DleqProof, ElGamalEncryption};
+use crate::error::{EnftError, EnftResult};
+use crate::key_derive::derive_private_key;
+use crate::threshold::{combine_partial_decryptions, KeyShare, ThresholdPublicKey};
+use crate::utils::{parse_bcs, parse_g1_element, parse_hex};
+use axum::extract::State;
+use axum::http::header::AUTHORIZATION;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use fastcrypto::encoding::{Encoding, Hex};
+use fastcrypto::groups::bls12381::{G1Element, Scalar};
+use fastcrypto::groups::GroupElement;
+use fastcrypto::serde_helpers::ToFromByteArray;
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use reqwest::blocking::RequestBuilder;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// The minimum length of the master key of a key server.
+pub const MIN_MASTER_KEY_LENGTH: usize = 32;
+
+/// The claims of a token that identify the application and the user.
+#[derive(Debug, Deserialize)]
+struct Claims {
+    iss: String,
+    aud: String,
+    sub: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublicKeyResponse {
+    /// The hex encoded encryption pubkey of the user. For a threshold server this is
+    /// the pubkey of its share of the user's key.
+    pub public_key: String,
+    /// The index of the server's share, for a threshold server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub share_index: Option<u16>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PartialDecryptRequest {
+    /// The hex encoded ephemeral key of an encryption under the user's pubkey.
+    pub ephemeral: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PartialDecryptResponse {
+    /// The hex encoded partial decryption `ephemeral * sk`.
+    pub partial: String,
+    /// A hex encoded BCS DleqProof that the user's key was used.
+    pub proof: String,
+}
+
+/// A token issuer that the key server trusts, with the keys that its tokens are
+/// signed with. A key only authenticates tokens whose `iss` claim is this issuer.
+#[derive(Clone, Debug)]
+pub struct TrustedIssuer {
+    pub iss: String,
+    pub jwks: JwkSet,
+}
+
+impl TrustedIssuer {
+    /// Parse `<iss>=<jwks file>` and load the JWKS from the file.
+    pub fn from_arg(arg: &str) -> EnftResult<Self> {
+        let (iss, path) = arg.split_once('=').ok_or_else(|| {
+            EnftError::KeyServer(format!(
+                "Invalid issuer '{}', expected <iss>=<jwks file>",
+                arg
+            ))
+        })?;
+        Ok(TrustedIssuer {
+            iss: iss.to_string(),
+            jwks: load_jwks(path)?,
+        })
+    }
+}
+
+/// A key server holding a master key, from which the encryption key of each user
+/// is derived with [derive_private_key]. Users authenticate with JWTs signed by a
+/// key of a [TrustedIssuer], and are identified by the `iss`, `aud` and `sub` claims. The
+/// user keys never leave the server: it only returns partial decryptions, with a
+/// proof that they were computed with the key of the user's pubkey.
+///
+/// In threshold mode the server holds one share of a Shamir-shared master secret
+/// instead, see [crate::threshold], and its partial decryptions must be combined
+/// with those of other servers by a [ThresholdClient].
+pub struct KeyServer {
+    key: ServerKey,
+    issuers: Vec<TrustedIssuer>,
+}
+
+enum ServerKey {
+    Master(Vec<u8>),
+    Share(KeyShare),
+}
+
+impl KeyServer {
+    pub fn new(master_key: Vec<u8>, issuers: Vec<TrustedIssuer>) -> Self {
+        KeyServer {
+            key: ServerKey::Master(master_key),
+            issuers,
+        }
+    }
+
+    /// A threshold key server holding one share of the master secret.
+    pub fn with_share(share: KeyShare, issuers: Vec<TrustedIssuer>) -> Self {
+        KeyServer {
+            key: ServerKey::Share(share),
+            issuers,
+        }
+    }
+
+    /// Load the hex encoded master key from a file. The key must have at least
+    /// [MIN_MASTER_KEY_LENGTH] bytes, since every user key is derived from it.
+    pub fn from_files(
+        master_key_path: impl AsRef<Path>,
+        issuers: Vec<TrustedIssuer>,
+    ) -> EnftResult<Self> {
+        let master_key = parse_hex(std::fs::read_to_string(master_key_path)?.trim())?;
+        if master_key.len() < MIN_MASTER_KEY_LENGTH {
+            return Err(EnftError::InvalidLength {
+                expected: MIN_MASTER_KEY_LENGTH,
+                actual: master_key.len(),
+            });
+        }
+        Ok(KeyServer::new(master_key, issuers))
+    }
+
+    /// Load a key share written by `enft-cli deal-key-shares` from a file.
+    pub fn from_share_files(
+        share_path: impl AsRef<Path>,
+        issuers: Vec<TrustedIssuer>,
+    ) -> EnftResult<Self> {
+        let share = KeyShare::from_json_file(share_path)?;
+        Ok(KeyServer::with_share(share, issuers))
+    }
+
+    /// The HTTP routes of the key server.
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/v1/public_key", get(public_key))
+            .route("/v1/partial_decrypt", post(partial_decrypt))
+            .with_state(Arc::new(self))
+    }
+
+    /// Verify a JWT against the keys of the trusted issuers and return the
+    /// (app_id, user_id) of the user. The token must be signed with the algorithm of
+    /// the key, and its `iss` claim must be the issuer of the key.
+    pub fn authenticate(&self, token: &str) -> EnftResult<(Vec<u8>, Vec<u8>)> {
+        let header = decode_header(token).map_err(unauthorized)?;
+        let keys: Vec<(&TrustedIssuer, &Jwk)> = self
+            .issuers
+            .iter()
+            .flat_map(|issuer| issuer.jwks.keys.iter().map(move |jwk| (issuer, jwk)))
+            .collect();
+        let (issuer, jwk) = match &header.kid {
+            Some(kid) => keys
+                .into_iter()
+                .find(|(_, jwk)| jwk.common.key_id.as_ref() == Some(kid)),
+            None if keys.len() == 1 => keys.into_iter().next(),
+            None => None,
+        }
+        .ok_or_else(|| EnftError::Unauthorized("Unknown signing key".to_string()))?;
+
+        // The algorithm is the one of the key, not the one the token asks for.
+        let algorithm = jwk
+            .common
+            .key_algorithm
+            .and_then(|alg| Algorithm::from_str(&alg.to_string()).ok())
+            .ok_or_else(|| {
+                EnftError::Unauthorized("The signing key has no signature algorithm".to_string())
+            })?;
+        if header.alg != algorithm {
+            return Err(EnftError::Unauthorized(format!(
+                "The token is signed with {:?} but the key uses {:?}",
+                header.alg, algorithm
+            )));
+        }
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[&issuer.iss]);
+        // Any audience is accepted, it selects the application.
+        validation.validate_aud = false;
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let claims = decode::<Claims>(
+            token,
+            &DecodingKey::from_jwk(jwk).map_err(unauthorized)?,
+            &validation,
+        )
+        .map_err(unauthorized)?
+        .claims;
+        Ok((app_id(&claims.iss, &claims.aud)?, claims.sub.into_bytes()))
+    }
+
+    /// The encryption private key of the user authenticated by the request headers.
+    fn user_key(&self, headers: &HeaderMap) -> EnftResult<Scalar> {
+        let token = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| EnftError::Unauthorized("Missing bearer token".to_string()))?;
+        let (app_id, user_id) = self.authenticate(token)?;
+        Ok(match &self.key {
+            ServerKey::Master(master_key) => derive_private_key(master_key, &app_id, &user_id),
+            ServerKey::Share(share) => share.user_key_share(&app_id, &user_id),
+        })
+    }
+
+    fn share_index(&self) -> Option<u16> {
+        match &self.key {
+            ServerKey::Master(_) => None,
+            ServerKey::Share(share) => Some(share.index),
+        }
+    }
+}
+
+fn unauthorized(e: jsonwebtoken::errors::Error) -> EnftError {
+    EnftError::Unauthorized(e.to_string())
+}
+
+fn load_jwks(path: impl AsRef<Path>) -> EnftResult<JwkSet> {
+    serde_json::from_slice(&std::fs::read(path)?).map_err(|e| EnftError::Io(e.into()))
+}
+
+/// The app_id of an application: `iss_len || iss || aud_len || aud` with one byte lengths.
+pub fn app_id(iss: &str, aud: &str) -> EnftResult<Vec<u8>> {
+    let mut app_id = vec![];
+    for claim in [iss, aud] {
+        let len = u8::try_from(claim.len())
+            .map_err(|_| EnftError::Unauthorized("Claim is too long".to_string()))?;
+        app_id.push(len);
+        app_id.extend_from_slice(claim.as_bytes());
+    }
+    Ok(app_id)
+}
+
+/// The (app_id, user_id) that a token claims, without verifying it. The key servers
+/// verify the token; clients only need this to compute the user's pubkey.
+pub fn token_identity(token: &str) -> EnftResult<(Vec<u8>, Vec<u8>)> {
+    let mut validation = Validation::default();
+    validation.insecure_disable_signature_validation();
+    validation.validate_aud = false;
+    validation.validate_exp = false;
+    let claims = decode::<Claims>(token, &DecodingKey::from_secret(&[]), &validation)
+        .map_err(|e| EnftError::Unauthorized(e.to_string()))?
+        .claims;
+    Ok((app_id(&claims.iss, &claims.aud)?, claims.sub.into_bytes()))
+}
+
+async fn public_key(
+    State(server): State<Arc<KeyServer>>,
+    headers: HeaderMap,
+) -> Result<Json<PublicKeyResponse>, ApiError> {
+    let sk = server.user_key(&headers)?;
+    Ok(Json(PublicKeyResponse {
+        public_key: Hex::encode((G1Element::generator() * sk).to_byte_array()),
+        share_index: server.share_index(),
+    }))
+}
+
+async fn partial_decrypt(
+    State(server): State<Arc<KeyServer>>,
+    headers: HeaderMap,
+    Json(request): Json<PartialDecryptRequest>,
+) -> Result<Json<PartialDecryptResponse>, ApiError> {
+    let sk = server.user_key(&headers)?;
+    let ephemeral = parse_g1_element(&request.ephemeral)?;
+    let (partial, proof) = elgamal::partial_decrypt(&ephemeral, &sk, &mut rand::thread_rng());
+    Ok(Json(PartialDecryptResponse {
+        partial: Hex::encode(partial.to_byte_array()),
+        proof: Hex::encode(bcs::to_bytes(&proof).unwrap()),
+    }))
+}
+
+/// A client of the key server, authenticated with a JWT.
+pub struct KeyServerClient {
+    url: String,
+    token: String,
+    client: reqwest::blocking::Client,
+}
+
+impl KeyServerClient {
+    pub fn new(url: &str, token: &str) -> Self {
+        KeyServerClient {
+            url: url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+
+    /// The encryption pubkey of the user, as reported by the key server.
+    pub fn public_key(&self) -> EnftResult<G1Element> {
+        parse_g1_element(&self.info()?.public_key)
+    }
+
+    /// The index of the key share held by a threshold key server.
+    pub fn share_index(&self) -> EnftResult<u16> {
+        self.info()?.share_index.ok_or_else(|| {
+            EnftError::Threshold(format!("{} is not a threshold key server", self.url))
+        })
+    }
+
+    fn info(&self) -> EnftResult<PublicKeyResponse> {
+        self.send(self.client.get(format!("{}/v1/public_key", self.url)))
+    }
+
+    /// Ask the key server for the partial decryption of an ephemeral key, and check
+    /// its proof against the user's published pubkey.
+    pub fn partial_decrypt(&self, ephemeral: &G1Element, pk: &G1Element) -> EnftResult<G1Element> {
+        let request = self
+            .client
+            .post(format!("{}/v1/partial_decrypt", self.url))
+            .json(&PartialDecryptRequest {
+                ephemeral: Hex::encode(ephemeral.to_byte_array()),
+            });
+        let response: PartialDecryptResponse = self.send(request)?;
+        let partial = parse_g1_element(&response.partial)?;
+        let proof: DleqProof = parse_bcs(&response.proof)?;
+        elgamal::verify_partial_decryption(pk, ephemeral, &partial, &proof)?;
+        Ok(partial)
+    }
+
+    /// Decrypt a master key encrypted under `pk` with the help of the key server.
+    pub fn decrypt(&self, enc: &ElGamalEncryption, pk: &G1Element) -> EnftResult<G1Element> {
+        let partial = self.partial_decrypt(&enc.ephemeral, pk)?;
+        Ok(elgamal::decrypt_with_partial(enc, &partial))
+    }
+
+    fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> EnftResult<T> {
+        let response = request
+            .bearer_auth(&self.token)
+            .send()
+            .map_err(|e| EnftError::KeyServer(e.to_string()))?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response
+                .json::<serde_json::Value>()
+                .ok()
+                .and_then(|body| body["error"].as_str().map(str::to_string))
+                .unwrap_or_else(|| status.to_string());
+            return Err(match status {
+                StatusCode::UNAUTHORIZED => EnftError::Unauthorized(message),
+                _ => EnftError::KeyServer(message),
+            });
+        }
+        response
+            .json()
+            .map_err(|e| EnftError::KeyServer(e.to_string()))
+    }
+}
+
+/// A client of threshold key servers, each holding a share of the master secret.
+pub struct ThresholdClient {
+    public_key: ThresholdPublicKey,
+    servers: Vec<KeyServerClient>,
+}
+
+impl ThresholdClient {
+    pub fn new(public_key: ThresholdPublicKey, urls: &[String], token: &str) -> Self {
+        ThresholdClient {
+            public_key,
+            servers: urls
+                .iter()
+                .map(|url| KeyServerClient::new(url, token))
+                .collect(),
+        }
+    }
+
+    /// Decrypt a master key encrypted under `pk`, which must be the user's pubkey under
+    /// the threshold public key. Partial decryptions are collected until `threshold` of
+    /// them have valid proofs; servers that fail or return invalid proofs are skipped.
+    pub fn decrypt(&self, enc: &ElGamalEncryption, pk: &G1Element) -> EnftResult<G1Element> {
+        let token = match self.servers.first() {
+            Some(server) => &server.token,
+            None => return Err(EnftError::Threshold("No key servers given".to_string())),
+        };
+        let (app_id, user_id) = token_identity(token)?;
+        if self.public_key.user_public_key(&app_id, &user_id) != *pk {
+            return Err(EnftError::WrongKey);
+        }
+
+        let threshold = self.public_key.threshold as usize;
+        let mut partials: Vec<(u16, G1Element)> = vec![];
+        let mut errors = vec![];
+        for server in &self.servers {
+            if partials.len() == threshold {
+                break;
+            }
+            let partial = server.share_index().and_then(|index| {
+                if partials.iter().any(|(i, _)| *i == index) {
+                    return Err(EnftError::Threshold(format!("Duplicate share {}", index)));
+                }
+                let pk_share = self
+                    .public_key
+                    .user_public_key_share(index, &app_id, &user_id)?;
+                Ok((index, server.partial_decrypt(&enc.ephemeral, &pk_share)?))
+            });
+            match partial {
+                Ok(partial) => partials.push(partial),
+                Err(e) => errors.push(format!("{}: {}", server.url, e)),
+            }
+        }
+        if partials.len() < threshold {
+            return Err(EnftError::Threshold(format!(
+                "Only {} of {} required partial decryptions ({})",
+                partials.len(),
+                threshold,
+                errors.join("; ")
+            )));
+        }
+        let partial = combine_partial_decryptions(self.public_key.threshold, &partials)?;
+        Ok(elgamal::decrypt_with_partial(enc, &partial))
+    }
+}
+
+/// An error returned as a JSON body `{"error": <message>}`.
+struct ApiError(EnftError);
+
+impl From<EnftError> for ApiError {
+    fn from(e: EnftError) -> Self {
+        ApiError(e)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self.0 {
+            EnftError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        (
+            status,
+            Json(serde_json::json!({ "error": self.0.to_string() })),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::threshold::deal;
+    use axum::body::Body;
+    use axum::http::Request;
+    use fastcrypto::groups::Scalar as _;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use tower::ServiceExt;
+
+    const SECRET: &[u8] = b"enft-keyserver-test-secret-0123456789";
+    const ISSUER: &str = "https://issuer.example";
+    const OTHER_SECRET: &[u8] = b"enft-keyserver-other-issuer-secret-0123";
+    const OTHER_ISSUER: &str = "https://other.example";
+
+    fn issuer(iss: &str, kid: &str, k: &str) -> TrustedIssuer {
+        let jwks = serde_json::json!({
+            "keys": [{
+                "kty": "oct",
+                "kid": kid,
+                "alg": "HS256",
+                "k": k
+            }]
+        });
+        TrustedIssuer {
+            iss: iss.to_string(),
+            jwks: serde_json::from_value(jwks).unwrap(),
+        }
+    }
+
+    fn issuers() -> Vec<TrustedIssuer> {
+        vec![
+            issuer(
+                ISSUER,
+                "test",
+                "ZW5mdC1rZXlzZXJ2ZXItdGVzdC1zZWNyZXQtMDEyMzQ1Njc4OQ",
+            ),
+            issuer(
+                OTHER_ISSUER,
+                "other",
+                "ZW5mdC1rZXlzZXJ2ZXItb3RoZXItaXNzdWVyLXNlY3JldC0wMTIz",
+            ),
+        ]
+    }
+
+    fn server() -> KeyServer {
+        KeyServer::new(vec![0; 32], issuers())
+    }
+
+    /// Serve on a free local port and return the URL.
+    fn spawn(runtime: &tokio::runtime::Runtime, server: KeyServer) -> String {
+        let listener = runtime
+            .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+            .unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        runtime.spawn(async move { axum::serve(listener, server.router()).await });
+        url
+    }
+
+    fn token(secret: &[u8], exp_offset: i64) -> String {
+        signed_token("test", Algorithm::HS256, secret, ISSUER, exp_offset)
+    }
+
+    fn signed_token(
+        kid: &str,
+        alg: Algorithm,
+        secret: &[u8],
+        iss: &str,
+        exp_offset: i64,
+    ) -> String {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let header = Header {
+            kid: Some(kid.to_string()),
+            ..Header::new(alg)
+        };
+        let claims = serde_json::json!({
+            "iss": iss,
+            "aud": "example_app",
+            "sub": "0",
+            "exp": now + exp_offset,
+        });
+        encode(&header, &claims, &EncodingKey::from_secret(secret)).unwrap()
+    }
+
+    async fn call(request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = server().router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn get_public_key(token: &str) -> Request<Body> {
+        Request::get("/v1/public_key")
+            .header(AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_client_decrypt() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let url = spawn(&runtime, server());
+
+        let sk = derive_private_key(&[0; 32], &app_id(ISSUER, "example_app").unwrap(), b"0");
+        let pk = G1Element::generator() * sk;
+        let client = KeyServerClient::new(&url, &token(SECRET, 600));
+        assert_eq!(client.public_key().unwrap(), pk);
+
+        let mut rng = rand::thread_rng();
+        let msk = G1Element::generator() * Scalar::rand(&mut rng);
+        let (enc_msk, _) = elgamal::encrypt(&msk, &pk, &mut rng);
+        assert_eq!(client.decrypt(&enc_msk, &pk).unwrap(), msk);
+
+        // The proof is checked against the published pubkey.
+        let other_pk = G1Element::generator() * Scalar::rand(&mut rng);
+        assert!(matches!(
+            client.decrypt(&enc_msk, &other_pk),
+            Err(EnftError::ProofRejected(_))
+        ));
+
+        let client = KeyServerClient::new(&url, &token(SECRET, -600));
+        assert!(matches!(
+            client.public_key(),
+            Err(EnftError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn test_threshold_decrypt() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let mut rng = rand::thread_rng();
+        let (mut shares, public_key) = deal(2, 3, &mut rng).unwrap();
+        // The first server uses a wrong share, so its proofs do not verify.
+        shares[0].share = Scalar::rand(&mut rng);
+        let urls: Vec<String> = shares
+            .into_iter()
+            .map(|share| spawn(&runtime, KeyServer::with_share(share, issuers())))
+            .collect();
+
+        let token = token(SECRET, 600);
+        let (app_id, user_id) = token_identity(&token).unwrap();
+        let pk = public_key.user_public_key(&app_id, &user_id);
+        let msk = G1Element::generator() * Scalar::rand(&mut rng);
+        let (enc_msk, _) = elgamal::encrypt(&msk, &pk, &mut rng);
+
+        let client = ThresholdClient::new(public_key.clone(), &urls, &token);
+        assert_eq!(client.decrypt(&enc_msk, &pk).unwrap(), msk);
+        assert!(matches!(
+            client.decrypt(&enc_msk, &(G1Element::generator() * Scalar::rand(&mut rng))),
+            Err(EnftError::WrongKey)
+        ));
+
+        // The bad server and one good one are not enough.
+        let client = ThresholdClient::new(public_key, &urls[..2], &token);
+        assert!(matches!(
+            client.decrypt(&enc_msk, &pk),
+            Err(EnftError::Threshold(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_short_master_key() {
+        let path = std::env::temp_dir().join(format!("enft-master-key-{}", rand::random::<u64>()));
+        for length in [0, MIN_MASTER_KEY_LENGTH - 1, MIN_MASTER_KEY_LENGTH] {
+            std::fs::write(&path, "ab".repeat(length)).unwrap();
+            assert_eq!(
+                KeyServer::from_files(&path, issuers()).is_ok(),
+                length == MIN_MASTER_KEY_LENGTH
+            );
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_tokens() {
+        for token in [
+            token(b"another-secret-that-is-not-in-the-jwks", 600),
+            token(SECRET, -600),
+            "not a token".to_string(),
+            // The key is for HS256, so another algorithm is rejected.
+            signed_token("test", Algorithm::HS384, SECRET, ISSUER, 600),
+            // The key of one issuer cannot sign tokens of another.
+            signed_token("other", Algorithm::HS256, OTHER_SECRET, ISSUER, 600),
+            signed_token("test", Algorithm::HS256, SECRET, OTHER_ISSUER, 600),
+        ] {
+            let (status, _) = call(get_public_key(&token)).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+        }
+
+        // Each issuer authenticates its own tokens.
+        let token = signed_token("other", Algorithm::HS256, OTHER_SECRET, OTHER_ISSUER, 600);
+        assert_eq!(call(get_public_key(&token)).await.0, StatusCode::OK);
+
+        let request = Request::get("/v1/public_key").body(Body::empty()).unwrap();
+        assert_eq!(call(request).await.0, StatusCode::UNAUTHORIZED);
+    }
+}
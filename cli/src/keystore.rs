@@ -0,0 +1,342 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::{EnftError, EnftResult};
+use crate::utils::{g1_element_from_bytes, scalar_from_bytes};
+use fastcrypto::aes::{Aes256Gcm, AesKey, AuthenticatedCipher, InitializationVector};
+use fastcrypto::encoding::{Encoding, Hex};
+use fastcrypto::groups::bls12381::{G1Element, Scalar};
+use fastcrypto::groups::{GroupElement, Scalar as _};
+use fastcrypto::serde_helpers::ToFromByteArray;
+use fastcrypto::traits::{AllowedRng, Generate, ToFromBytes};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use typenum::U12;
+
+/// The environment variable overriding the default keystore path.
+pub const KEYSTORE_ENV: &str = "ENFT_KEYSTORE";
+
+/// The kind of secret stored under a name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyKind {
+    /// An encryption private key (a scalar), e.g. for `--buyer-sk` or `--seller-enc-sk`.
+    Encryption,
+    /// The master key of an NFT (a G1 element), e.g. for `--master-sk`.
+    Master,
+    /// A key server master secret for `derive-encryption-key`.
+    Derivation,
+}
+
+impl KeyKind {
+    /// Check that the secret is a valid key of this kind, and return its public
+    /// key if there is one.
+    fn validate(&self, secret: &[u8]) -> EnftResult<Option<G1Element>> {
+        match self {
+            KeyKind::Encryption => Ok(Some(
+                G1Element::generator() * scalar_from_bytes(secret.to_vec())?,
+            )),
+            KeyKind::Master => g1_element_from_bytes(secret.to_vec()).map(|_| None),
+            KeyKind::Derivation => Ok(None),
+        }
+    }
+
+    /// Generate a fresh secret of this kind.
+    pub fn generate<R: AllowedRng>(&self, rng: &mut R) -> Vec<u8> {
+        match self {
+            KeyKind::Encryption => Scalar::rand(rng).to_byte_array().to_vec(),
+            KeyKind::Master => (G1Element::generator() * Scalar::rand(rng))
+                .to_byte_array()
+                .to_vec(),
+            KeyKind::Derivation => {
+                let mut secret = vec![0u8; 32];
+                rng.fill_bytes(&mut secret);
+                secret
+            }
+        }
+    }
+}
+
+impl fmt::Display for KeyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyKind::Encryption => write!(f, "encryption"),
+            KeyKind::Master => write!(f, "master"),
+            KeyKind::Derivation => write!(f, "derivation"),
+        }
+    }
+}
+
+impl FromStr for KeyKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "encryption" => Ok(KeyKind::Encryption),
+            "master" => Ok(KeyKind::Master),
+            "derivation" => Ok(KeyKind::Derivation),
+            _ => Err(format!(
+                "Unknown key kind '{}', expected one of encryption, master, derivation",
+                s
+            )),
+        }
+    }
+}
+
+/// The scrypt parameters used to derive the AES key of an entry from the password.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for KdfParams {
+    /// The parameters recommended by the scrypt crate for interactive use.
+    fn default() -> Self {
+        KdfParams {
+            log_n: 17,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+/// A secret encrypted under a password. All byte strings are hex encoded.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeystoreEntry {
+    pub kind: KeyKind,
+    /// The public key for encryption keys, so keys can be listed without the password.
+    pub public_key: Option<String>,
+    pub kdf: KdfParams,
+    pub salt: String,
+    pub iv: String,
+    pub ciphertext: String,
+}
+
+/// A JSON file with named secrets, each encrypted with AES-256-GCM under a key
+/// derived from a password with scrypt. The name and kind of an entry are bound
+/// to its ciphertext as associated data.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Keystore {
+    #[serde(skip)]
+    kdf: KdfParams,
+    keys: BTreeMap<String, KeystoreEntry>,
+}
+
+impl Keystore {
+    /// The keystore path from `ENFT_KEYSTORE`, or `~/.enft/keystore.json`.
+    pub fn default_path() -> PathBuf {
+        match std::env::var_os(KEYSTORE_ENV) {
+            Some(path) => PathBuf::from(path),
+            None => PathBuf::from(std::env::var_os("HOME").unwrap_or_default())
+                .join(".enft")
+                .join("keystore.json"),
+        }
+    }
+
+    /// Read a keystore, or start an empty one if the file does not exist.
+    pub fn load(path: impl AsRef<Path>) -> EnftResult<Self> {
+        match std::fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| EnftError::Keystore(format!("Invalid keystore file: {}", e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Keystore::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the keystore, readable by the owner only. It is written to a temporary file
+    /// that replaces the keystore, so an existing keystore with looser permissions is
+    /// not kept and a failed write leaves the old keystore intact.
+    pub fn save(&self, path: impl AsRef<Path>) -> EnftResult<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options.open(&tmp_path)?;
+        // The mode only applies to new files, so a leftover temporary file is restricted too.
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        serde_json::to_writer_pretty(&file, self).map_err(|e| EnftError::Io(e.into()))?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Use other scrypt parameters for new entries.
+    pub fn with_kdf(mut self, kdf: KdfParams) -> Self {
+        self.kdf = kdf;
+        self
+    }
+
+    /// The names, kinds and public keys of all entries.
+    pub fn list(&self) -> impl Iterator<Item = (&String, &KeystoreEntry)> {
+        self.keys.iter()
+    }
+
+    /// Encrypt a secret under the password and store it under a new name.
+    pub fn add<R: AllowedRng>(
+        &mut self,
+        name: &str,
+        kind: KeyKind,
+        secret: &[u8],
+        password: &str,
+        rng: &mut R,
+    ) -> EnftResult<()> {
+        if self.keys.contains_key(name) {
+            return Err(EnftError::Keystore(format!(
+                "Key '{}' already exists",
+                name
+            )));
+        }
+        let public_key = kind.validate(secret)?;
+
+        let mut salt = [0u8; 16];
+        rng.fill_bytes(&mut salt);
+        let iv = InitializationVector::<U12>::generate(rng);
+        let ciphertext = password_cipher(password, &salt, &self.kdf)?.encrypt_authenticated(
+            &iv,
+            &associated_data(name, kind),
+            secret,
+        );
+        self.keys.insert(
+            name.to_string(),
+            KeystoreEntry {
+                kind,
+                public_key: public_key.map(|pk| Hex::encode(pk.to_byte_array())),
+                kdf: self.kdf,
+                salt: Hex::encode(salt),
+                iv: Hex::encode(iv.as_ref()),
+                ciphertext: Hex::encode(ciphertext),
+            },
+        );
+        Ok(())
+    }
+
+    /// Decrypt the secret stored under a name, which must be of the given kind.
+    pub fn get(&self, name: &str, kind: KeyKind, password: &str) -> EnftResult<Vec<u8>> {
+        let entry = self
+            .keys
+            .get(name)
+            .ok_or_else(|| EnftError::Keystore(format!("No key named '{}'", name)))?;
+        if entry.kind != kind {
+            return Err(EnftError::Keystore(format!(
+                "Key '{}' is a {} key, expected a {} key",
+                name, entry.kind, kind
+            )));
+        }
+        let decode = |s: &str| Hex::decode(s).map_err(|_| EnftError::InvalidHex);
+        let iv = InitializationVector::<U12>::from_bytes(&decode(&entry.iv)?)
+            .map_err(|_| EnftError::Keystore(format!("Invalid IV for key '{}'", name)))?;
+        password_cipher(password, &decode(&entry.salt)?, &entry.kdf)?
+            .decrypt_authenticated(
+                &iv,
+                &associated_data(name, kind),
+                &decode(&entry.ciphertext)?,
+            )
+            .map_err(|_| EnftError::Keystore(format!("Wrong password for key '{}'", name)))
+    }
+
+    /// Delete the entry with the given name.
+    pub fn remove(&mut self, name: &str) -> EnftResult<()> {
+        self.keys
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| EnftError::Keystore(format!("No key named '{}'", name)))
+    }
+}
+
+fn associated_data(name: &str, kind: KeyKind) -> Vec<u8> {
+    format!("enft-keystore:{}:{}", kind, name).into_bytes()
+}
+
+fn password_cipher(password: &str, salt: &[u8], kdf: &KdfParams) -> EnftResult<Aes256Gcm<U12>> {
+    let params = scrypt::Params::new(kdf.log_n, kdf.r, kdf.p, 32)
+        .map_err(|e| EnftError::Keystore(format!("Invalid scrypt parameters: {}", e)))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(password.as_bytes(), salt, &params, &mut key)
+        .map_err(|e| EnftError::Keystore(e.to_string()))?;
+    Ok(Aes256Gcm::<U12>::new(AesKey::from_bytes(&key).unwrap()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_keystore() {
+        let mut rng = rand::thread_rng();
+        let path =
+            std::env::temp_dir().join(format!("enft-keystore-{}.json", rand::random::<u64>()));
+        // Cheap parameters to keep the test fast.
+        let mut keystore = Keystore::default().with_kdf(KdfParams {
+            log_n: 4,
+            r: 8,
+            p: 1,
+        });
+
+        let sk = KeyKind::Encryption.generate(&mut rng);
+        keystore
+            .add("seller", KeyKind::Encryption, &sk, "hunter2", &mut rng)
+            .unwrap();
+        let msk = KeyKind::Master.generate(&mut rng);
+        keystore
+            .add("nft", KeyKind::Master, &msk, "hunter2", &mut rng)
+            .unwrap();
+        assert!(keystore
+            .add("nft", KeyKind::Master, &msk, "hunter2", &mut rng)
+            .is_err());
+        assert!(keystore
+            .add("bad", KeyKind::Encryption, &[0xff; 32], "hunter2", &mut rng)
+            .is_err());
+        keystore.save(&path).unwrap();
+
+        let mut keystore = Keystore::load(&path).unwrap();
+        assert_eq!(
+            keystore
+                .get("seller", KeyKind::Encryption, "hunter2")
+                .unwrap(),
+            sk
+        );
+        assert_eq!(
+            keystore.get("nft", KeyKind::Master, "hunter2").unwrap(),
+            msk
+        );
+        assert!(keystore
+            .get("seller", KeyKind::Encryption, "wrong")
+            .is_err());
+        assert!(keystore.get("nft", KeyKind::Encryption, "hunter2").is_err());
+
+        keystore.remove("nft").unwrap();
+        assert!(keystore.get("nft", KeyKind::Master, "hunter2").is_err());
+        assert_eq!(keystore.list().count(), 1);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_restricts_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path =
+            std::env::temp_dir().join(format!("enft-keystore-{}.json", rand::random::<u64>()));
+        std::fs::write(&path, "{}").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        Keystore::default().save(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(Keystore::load(&path).unwrap().list().count(), 0);
+        std::fs::remove_file(path).unwrap();
+    }
+}
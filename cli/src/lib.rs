@@ -1,19 +1,26 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use fastcrypto::aes::InitializationVector;
-use serde::{Deserialize, Serialize};
-use typenum::U12;
-
+pub mod blob;
+pub mod ciphertext;
+pub mod dkg;
+pub mod elgamal;
+pub mod error;
+pub mod inspect;
 pub mod key_derive;
+#[cfg(feature = "keyserver")]
+pub mod keyserver;
+pub mod keystore;
+pub mod marketplace;
+pub mod move_call;
+pub mod region;
+pub mod sampling;
+pub mod style;
+pub mod threshold;
 pub mod utils;
+pub mod vectors;
 
-#[derive(Clone, Serialize, Deserialize)]
-pub struct FullCipherText {
-    pub pixels: Vec<(usize, usize)>,
-    pub iv: InitializationVector<U12>,
-    pub data: Vec<u8>,
-}
+pub use ciphertext::FullCipherText;
 
 #[cfg(test)]
 mod test {
@@ -0,0 +1,369 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An offline model of the `private_nft` Move module, for driving listing, offer,
+//! accept and resale flows without a Sui node. The entry points take the same byte
+//! arguments as the Move functions and fail where the Move code would abort.
+
+use crate::elgamal::{verify_equality, ElGamalEncryption, EqualityProof};
+use crate::utils;
+use fastcrypto::groups::bls12381::{G1Element, Scalar};
+use fastcrypto::serde_helpers::ToFromByteArray;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// A Sui address.
+pub type Address = [u8; 32];
+
+/// The id of an object. Object ids are sequential here instead of derived from the
+/// transaction.
+pub type ObjectId = u64;
+
+/// The Move module that aborts with [E_PROVE_ERROR].
+pub const PRIVATE_NFT_MODULE: &str = "private_nft";
+
+/// `EProveError` in `private_nft.move`: the equality proof of a transfer is invalid.
+pub const E_PROVE_ERROR: u64 = 1;
+
+/// The Move module that aborts with [E_INVALID_INPUT].
+pub const GROUP_OPS_MODULE: &str = "group_ops";
+
+/// `EInvalidInput` in `sui::group_ops`: bytes are not a valid G1 element or scalar.
+pub const E_INVALID_INPUT: u64 = 1;
+
+/// Why a transaction failed. A failed transaction leaves the objects unchanged.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum ExecutionError {
+    #[error("Object {0} does not exist")]
+    ObjectNotFound(ObjectId),
+
+    #[error("Object {0} is not owned by the sender")]
+    NotOwner(ObjectId),
+
+    #[error("Move abort in {module} with code {code}")]
+    MoveAbort { module: &'static str, code: u64 },
+}
+
+impl ExecutionError {
+    fn invalid_input() -> Self {
+        ExecutionError::MoveAbort {
+            module: GROUP_OPS_MODULE,
+            code: E_INVALID_INPUT,
+        }
+    }
+
+    fn prove_error() -> Self {
+        ExecutionError::MoveAbort {
+            module: PRIVATE_NFT_MODULE,
+            code: E_PROVE_ERROR,
+        }
+    }
+}
+
+/// Same as struct EncryptedNFT in the Move package.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedNFT {
+    pub id: ObjectId,
+    pub name: String,
+    pub image_url: String,
+    pub ciphertext_url: String,
+    pub encrypted_master_key: ElGamalEncryption,
+}
+
+/// The arguments of `private_nft::new`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewArgs {
+    pub name: String,
+    pub image_url: String,
+    pub ciphertext_url: String,
+    pub ephemeral_v: Vec<u8>,
+    pub ciphertext_v: Vec<u8>,
+}
+
+/// The arguments of `private_nft::transfer_to` after the NFT itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransferToArgs {
+    pub to: Address,
+    pub sender_pub_key: Vec<u8>,
+    pub receiver_pub_key: Vec<u8>,
+    pub prev_ephemeral_v: Vec<u8>,
+    pub prev_ciphertext_v: Vec<u8>,
+    pub new_ephemeral_v: Vec<u8>,
+    pub new_ciphertext_v: Vec<u8>,
+    pub proof_s1: Vec<u8>,
+    pub proof_s2: Vec<u8>,
+    pub proof_u1: Vec<u8>,
+    pub proof_u2: Vec<u8>,
+    pub proof_v: Vec<u8>,
+}
+
+impl NewArgs {
+    pub fn new(
+        name: &str,
+        image_url: &str,
+        ciphertext_url: &str,
+        encrypted_master_key: &ElGamalEncryption,
+    ) -> Self {
+        NewArgs {
+            name: name.to_string(),
+            image_url: image_url.to_string(),
+            ciphertext_url: ciphertext_url.to_string(),
+            ephemeral_v: encrypted_master_key.ephemeral.to_byte_array().to_vec(),
+            ciphertext_v: encrypted_master_key.ciphertext.to_byte_array().to_vec(),
+        }
+    }
+}
+
+impl TransferToArgs {
+    /// The arguments for transferring an NFT encrypted under `sender_pk` with `prev_enc`
+    /// to `to`, re-encrypted under `receiver_pk` as `new_enc`, e.g. the output of
+    /// [crate::elgamal::transfer].
+    pub fn new(
+        to: Address,
+        sender_pk: &G1Element,
+        receiver_pk: &G1Element,
+        prev_enc: &ElGamalEncryption,
+        new_enc: &ElGamalEncryption,
+        proof: &EqualityProof,
+    ) -> Self {
+        TransferToArgs {
+            to,
+            sender_pub_key: sender_pk.to_byte_array().to_vec(),
+            receiver_pub_key: receiver_pk.to_byte_array().to_vec(),
+            prev_ephemeral_v: prev_enc.ephemeral.to_byte_array().to_vec(),
+            prev_ciphertext_v: prev_enc.ciphertext.to_byte_array().to_vec(),
+            new_ephemeral_v: new_enc.ephemeral.to_byte_array().to_vec(),
+            new_ciphertext_v: new_enc.ciphertext.to_byte_array().to_vec(),
+            proof_s1: proof.s1.to_byte_array().to_vec(),
+            proof_s2: proof.s2.to_byte_array().to_vec(),
+            proof_u1: proof.u1.to_byte_array().to_vec(),
+            proof_u2: proof.u2.to_byte_array().to_vec(),
+            proof_v: proof.v.to_byte_array().to_vec(),
+        }
+    }
+}
+
+/// The owned objects of the chain.
+#[derive(Clone, Debug, Default)]
+pub struct Marketplace {
+    objects: BTreeMap<ObjectId, (Address, EncryptedNFT)>,
+    next_id: ObjectId,
+}
+
+impl Marketplace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `private_nft::new` and transfer the NFT to the sender, like the frontend does.
+    pub fn new_nft(&mut self, sender: Address, args: NewArgs) -> Result<ObjectId, ExecutionError> {
+        let encrypted_master_key = ElGamalEncryption {
+            ephemeral: g1_from_bytes(&args.ephemeral_v)?,
+            ciphertext: g1_from_bytes(&args.ciphertext_v)?,
+        };
+        let id = self.next_id;
+        self.next_id += 1;
+        let nft = EncryptedNFT {
+            id,
+            name: args.name,
+            image_url: args.image_url,
+            ciphertext_url: args.ciphertext_url,
+            encrypted_master_key,
+        };
+        self.objects.insert(id, (sender, nft));
+        Ok(id)
+    }
+
+    /// Call `private_nft::transfer_to` on an NFT owned by the sender. Like the Move
+    /// code, this checks the proof against the previous encryption and sender pubkey
+    /// given in the arguments, and does not compare them with the encrypted master key
+    /// stored in the NFT.
+    pub fn transfer_to(
+        &mut self,
+        sender: Address,
+        id: ObjectId,
+        args: TransferToArgs,
+    ) -> Result<(), ExecutionError> {
+        match self.objects.get(&id) {
+            None => return Err(ExecutionError::ObjectNotFound(id)),
+            Some((owner, _)) if *owner != sender => return Err(ExecutionError::NotOwner(id)),
+            Some(_) => (),
+        }
+
+        // Parsed in the order of the Move code.
+        let proof = EqualityProof {
+            s1: scalar_from_bytes(&args.proof_s1)?,
+            s2: scalar_from_bytes(&args.proof_s2)?,
+            u1: g1_from_bytes(&args.proof_u1)?,
+            u2: g1_from_bytes(&args.proof_u2)?,
+            v: g1_from_bytes(&args.proof_v)?,
+        };
+        let new_enc_msk = ElGamalEncryption {
+            ephemeral: g1_from_bytes(&args.new_ephemeral_v)?,
+            ciphertext: g1_from_bytes(&args.new_ciphertext_v)?,
+        };
+        let prev_enc_msk = ElGamalEncryption {
+            ephemeral: g1_from_bytes(&args.prev_ephemeral_v)?,
+            ciphertext: g1_from_bytes(&args.prev_ciphertext_v)?,
+        };
+        verify_equality(
+            &g1_from_bytes(&args.sender_pub_key)?,
+            &g1_from_bytes(&args.receiver_pub_key)?,
+            &prev_enc_msk,
+            &new_enc_msk,
+            &proof,
+        )
+        .map_err(|_| ExecutionError::prove_error())?;
+
+        let (owner, nft) = self.objects.get_mut(&id).expect("Checked above");
+        nft.encrypted_master_key = new_enc_msk;
+        *owner = args.to;
+        Ok(())
+    }
+
+    pub fn nft(&self, id: ObjectId) -> Option<&EncryptedNFT> {
+        self.objects.get(&id).map(|(_, nft)| nft)
+    }
+
+    pub fn owner(&self, id: ObjectId) -> Option<Address> {
+        self.objects.get(&id).map(|(owner, _)| *owner)
+    }
+
+    /// The NFTs owned by an address, like `getOwnedNFTs` in the frontend.
+    pub fn owned_by(&self, address: Address) -> Vec<&EncryptedNFT> {
+        self.objects
+            .values()
+            .filter(|(owner, _)| *owner == address)
+            .map(|(_, nft)| nft)
+            .collect()
+    }
+}
+
+/// `bls12381::g1_from_bytes`, which aborts on invalid input.
+fn g1_from_bytes(bytes: &[u8]) -> Result<G1Element, ExecutionError> {
+    utils::g1_element_from_bytes(bytes.to_vec()).map_err(|_| ExecutionError::invalid_input())
+}
+
+/// `bls12381::scalar_from_bytes`, which aborts on invalid input.
+fn scalar_from_bytes(bytes: &[u8]) -> Result<Scalar, ExecutionError> {
+    utils::scalar_from_bytes(bytes.to_vec()).map_err(|_| ExecutionError::invalid_input())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elgamal;
+    use fastcrypto::groups::{GroupElement, Scalar as _};
+
+    #[test]
+    fn test_list_offer_accept_resale() {
+        let mut rng = rand::thread_rng();
+        let gen = G1Element::generator();
+        let (creator, buyer, reseller_buyer) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        let keys: Vec<Scalar> = (0..3).map(|_| Scalar::rand(&mut rng)).collect();
+        let pks: Vec<G1Element> = keys.iter().map(|sk| gen * *sk).collect();
+        let mut chain = Marketplace::new();
+
+        // The creator lists the NFT with the master key encrypted under their pubkey.
+        let msk = gen * Scalar::rand(&mut rng);
+        let (enc, _) = elgamal::encrypt(&msk, &pks[0], &mut rng);
+        let id = chain
+            .new_nft(creator, NewArgs::new("Capy", "image", "ciphertext", &enc))
+            .unwrap();
+        assert_eq!(chain.owned_by(creator).len(), 1);
+
+        // The buyer offers with their pubkey and the creator accepts by transferring.
+        let prev_enc = chain.nft(id).unwrap().encrypted_master_key.clone();
+        let (new_enc, proof) = elgamal::transfer(&msk, &prev_enc, &keys[0], &pks[1], &mut rng);
+        let args = TransferToArgs::new(buyer, &pks[0], &pks[1], &prev_enc, &new_enc, &proof);
+        assert_eq!(
+            chain.transfer_to(buyer, id, args.clone()),
+            Err(ExecutionError::NotOwner(id))
+        );
+        chain.transfer_to(creator, id, args).unwrap();
+        assert_eq!(chain.owner(id), Some(buyer));
+
+        // The buyer recovers the master key from the chain and resells. A proof for
+        // another master key aborts with EProveError and changes nothing.
+        let prev_enc = chain.nft(id).unwrap().encrypted_master_key.clone();
+        let recovered = elgamal::decrypt(&prev_enc, &keys[1]);
+        assert_eq!(recovered, msk);
+        let other_msk = gen * Scalar::rand(&mut rng);
+        let (bad_enc, bad_proof) =
+            elgamal::transfer(&other_msk, &prev_enc, &keys[1], &pks[2], &mut rng);
+        let args = TransferToArgs::new(
+            reseller_buyer,
+            &pks[1],
+            &pks[2],
+            &prev_enc,
+            &bad_enc,
+            &bad_proof,
+        );
+        assert_eq!(
+            chain.transfer_to(buyer, id, args),
+            Err(ExecutionError::MoveAbort {
+                module: PRIVATE_NFT_MODULE,
+                code: E_PROVE_ERROR
+            })
+        );
+        assert_eq!(chain.owner(id), Some(buyer));
+        assert_eq!(chain.nft(id).unwrap().encrypted_master_key, prev_enc);
+
+        let (new_enc, proof) =
+            elgamal::transfer(&recovered, &prev_enc, &keys[1], &pks[2], &mut rng);
+        let args = TransferToArgs::new(
+            reseller_buyer,
+            &pks[1],
+            &pks[2],
+            &prev_enc,
+            &new_enc,
+            &proof,
+        );
+        chain.transfer_to(buyer, id, args).unwrap();
+        assert_eq!(chain.owner(id), Some(reseller_buyer));
+        assert_eq!(
+            elgamal::decrypt(&chain.nft(id).unwrap().encrypted_master_key, &keys[2]),
+            msk
+        );
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let mut chain = Marketplace::new();
+        let gen = G1Element::generator();
+        let enc = ElGamalEncryption {
+            ephemeral: gen,
+            ciphertext: gen,
+        };
+        let mut args = NewArgs::new("Capy", "image", "ciphertext", &enc);
+        args.ephemeral_v.pop();
+        assert_eq!(
+            chain.new_nft([1u8; 32], args),
+            Err(ExecutionError::MoveAbort {
+                module: GROUP_OPS_MODULE,
+                code: E_INVALID_INPUT
+            })
+        );
+        assert_eq!(
+            chain.transfer_to(
+                [1u8; 32],
+                0,
+                TransferToArgs::new(
+                    [2u8; 32],
+                    &gen,
+                    &gen,
+                    &enc,
+                    &enc,
+                    &EqualityProof {
+                        s1: Scalar::generator(),
+                        s2: Scalar::generator(),
+                        u1: gen,
+                        u2: gen,
+                        v: gen,
+                    }
+                )
+            ),
+            Err(ExecutionError::ObjectNotFound(0))
+        );
+    }
+}
@@ -0,0 +1,238 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The pure arguments of the `private_nft` entry functions, as the BCS bytes of a Sui
+//! transaction or as JSON values for the TypeScript SDK, in the order of the Move
+//! parameters.
+
+use crate::error::{EnftError, EnftResult};
+use crate::marketplace::{Address, NewArgs, TransferToArgs};
+use fastcrypto::encoding::{Encoding, Hex};
+use serde::Serialize;
+
+/// The Move function `private_nft::new`.
+pub const NEW_FUNCTION: &str = "private_nft::new";
+
+/// The Move function `private_nft::transfer_to`. Its first argument is the NFT object,
+/// which is not a pure argument.
+pub const TRANSFER_TO_FUNCTION: &str = "private_nft::transfer_to";
+
+/// The value of a pure argument.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PureValue {
+    String(String),
+    Address(Address),
+    Bytes(Vec<u8>),
+}
+
+/// A named pure argument of a Move function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PureArg {
+    pub name: &'static str,
+    pub value: PureValue,
+}
+
+/// A pure argument as written by the CLI: the name and type of the Move parameter,
+/// the value as passed to `tx.pure` and the hex encoded BCS bytes.
+#[derive(Clone, Debug, Serialize)]
+pub struct PureArgJson {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub type_: &'static str,
+    pub value: serde_json::Value,
+    pub bcs: String,
+}
+
+impl PureValue {
+    /// The Move type of the value.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            PureValue::String(_) => "0x1::string::String",
+            PureValue::Address(_) => "address",
+            PureValue::Bytes(_) => "vector<u8>",
+        }
+    }
+
+    /// The BCS bytes of the value, which a transaction holds as a pure input.
+    pub fn to_bcs(&self) -> Vec<u8> {
+        match self {
+            PureValue::String(s) => bcs::to_bytes(s),
+            PureValue::Address(address) => bcs::to_bytes(address),
+            PureValue::Bytes(bytes) => bcs::to_bytes(bytes),
+        }
+        .expect("Serializing strings and bytes cannot fail")
+    }
+
+    /// The value as the TypeScript SDK takes it: strings as is, addresses as `0x` hex
+    /// and byte vectors as arrays of numbers.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            PureValue::String(s) => serde_json::Value::from(s.as_str()),
+            PureValue::Address(address) => {
+                serde_json::Value::from(format!("0x{}", Hex::encode(address)))
+            }
+            PureValue::Bytes(bytes) => serde_json::Value::from(bytes.as_slice()),
+        }
+    }
+}
+
+impl PureArg {
+    fn string(name: &'static str, value: &str) -> Self {
+        PureArg {
+            name,
+            value: PureValue::String(value.to_string()),
+        }
+    }
+
+    fn bytes(name: &'static str, value: &[u8]) -> Self {
+        PureArg {
+            name,
+            value: PureValue::Bytes(value.to_vec()),
+        }
+    }
+
+    pub fn to_json(&self) -> PureArgJson {
+        PureArgJson {
+            name: self.name,
+            type_: self.value.type_name(),
+            value: self.value.to_json(),
+            bcs: Hex::encode(self.value.to_bcs()),
+        }
+    }
+}
+
+impl NewArgs {
+    /// The pure arguments of `private_nft::new`.
+    pub fn pure_args(&self) -> Vec<PureArg> {
+        vec![
+            PureArg::string("name", &self.name),
+            PureArg::string("image_url", &self.image_url),
+            PureArg::string("ciphertext_url", &self.ciphertext_url),
+            PureArg::bytes("ephemeral_v", &self.ephemeral_v),
+            PureArg::bytes("ciphertext_v", &self.ciphertext_v),
+        ]
+    }
+}
+
+impl TransferToArgs {
+    /// The pure arguments of `private_nft::transfer_to` after the NFT object.
+    pub fn pure_args(&self) -> Vec<PureArg> {
+        vec![
+            PureArg {
+                name: "to",
+                value: PureValue::Address(self.to),
+            },
+            PureArg::bytes("sender_pub_key", &self.sender_pub_key),
+            PureArg::bytes("receiver_pub_key", &self.receiver_pub_key),
+            PureArg::bytes("prev_ephemeral_v", &self.prev_ephemeral_v),
+            PureArg::bytes("prev_ciphertext_v", &self.prev_ciphertext_v),
+            PureArg::bytes("new_ephemeral_v", &self.new_ephemeral_v),
+            PureArg::bytes("new_ciphertext_v", &self.new_ciphertext_v),
+            PureArg::bytes("proof_s1", &self.proof_s1),
+            PureArg::bytes("proof_s2", &self.proof_s2),
+            PureArg::bytes("proof_u1", &self.proof_u1),
+            PureArg::bytes("proof_u2", &self.proof_u2),
+            PureArg::bytes("proof_v", &self.proof_v),
+        ]
+    }
+}
+
+/// Parse a Sui address in hex with an optional `0x` prefix. Short addresses like
+/// `0x2` are padded with leading zeros.
+pub fn parse_address(s: &str) -> EnftResult<Address> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    if s.len() > 64 {
+        return Err(EnftError::InvalidLength {
+            expected: 32,
+            actual: s.len().div_ceil(2),
+        });
+    }
+    let bytes = Hex::decode(&format!("{:0>64}", s)).map_err(|_| EnftError::InvalidHex)?;
+    Ok(bytes.try_into().expect("64 hex characters are 32 bytes"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elgamal::{transfer, ElGamalEncryption, EqualityProof};
+    use fastcrypto::groups::bls12381::{G1Element, Scalar};
+    use fastcrypto::groups::{GroupElement, Scalar as _};
+
+    const PRIVATE_NFT_MOVE: &str = include_str!("../../package/sources/private_nft.move");
+
+    /// The parameter names of a Move function, or the field names of a Move struct.
+    fn move_names(declaration: &str) -> Vec<String> {
+        let start = PRIVATE_NFT_MOVE.find(declaration).unwrap() + declaration.len();
+        let body = &PRIVATE_NFT_MOVE[start..];
+        let end = body.find([')', '}']).unwrap();
+        body[..end]
+            .split(',')
+            .map(|item| item.split_once(':').map_or(item, |(name, _)| name))
+            // Skip comments that follow the previous item.
+            .map(|name| name.lines().last().unwrap_or_default().trim().to_string())
+            .filter(|name| !name.is_empty() && !name.starts_with("//"))
+            .collect()
+    }
+
+    #[test]
+    fn test_move_parameter_order() {
+        let gen = G1Element::generator();
+        let mut rng = rand::thread_rng();
+        let sk = Scalar::rand(&mut rng);
+        let msk = gen * Scalar::rand(&mut rng);
+        let (enc, _) = crate::elgamal::encrypt(&msk, &(gen * sk), &mut rng);
+        let (new_enc, proof) = transfer(&msk, &enc, &sk, &gen, &mut rng);
+
+        let names = |args: Vec<PureArg>| args.iter().map(|a| a.name).collect::<Vec<_>>();
+        let new_args = NewArgs::new("Capy", "image", "ciphertext", &enc);
+        let mut expected = names(new_args.pure_args());
+        expected.push("ctx");
+        assert_eq!(move_names("public fun new("), expected);
+
+        let transfer_args = TransferToArgs::new([7; 32], &(gen * sk), &gen, &enc, &new_enc, &proof);
+        let mut expected = names(transfer_args.pure_args());
+        expected.insert(0, "self");
+        expected.push("_ctx");
+        assert_eq!(move_names("public fun transfer_to("), expected);
+
+        // The BCS of the Rust structs follows the field order of the Move structs, so the
+        // CLI artifacts split into the arguments in the same order.
+        assert_eq!(
+            move_names("struct ElGamalEncryption has drop, copy, store {"),
+            ["ephemeral", "ciphertext"]
+        );
+        let bcs_args: Vec<u8> = new_args.pure_args()[3..]
+            .iter()
+            .flat_map(|a| a.value.to_bcs()[1..].to_vec())
+            .collect();
+        assert_eq!(bcs::to_bytes::<ElGamalEncryption>(&enc).unwrap(), bcs_args);
+        assert_eq!(
+            move_names("struct EqualityProof has drop, store {"),
+            ["s1", "s2", "u1", "u2", "v"]
+        );
+        let bcs_args: Vec<u8> = transfer_args.pure_args()[7..]
+            .iter()
+            .flat_map(|a| a.value.to_bcs()[1..].to_vec())
+            .collect();
+        assert_eq!(bcs::to_bytes::<EqualityProof>(&proof).unwrap(), bcs_args);
+    }
+
+    #[test]
+    fn test_pure_values() {
+        let arg = PureArg::string("name", "Capy");
+        assert_eq!(arg.value.to_bcs(), b"\x04Capy");
+        let arg = PureArg::bytes("proof_s1", &[1, 2, 3]);
+        assert_eq!(arg.value.to_bcs(), [3, 1, 2, 3]);
+        assert_eq!(arg.to_json().value, serde_json::json!([1, 2, 3]));
+
+        let address = parse_address("0x2").unwrap();
+        assert_eq!(address[31], 2);
+        assert_eq!(PureValue::Address(address).to_bcs(), address);
+        assert_eq!(
+            PureValue::Address(address).to_json(),
+            serde_json::json!(format!("0x{}{}", "0".repeat(63), 2))
+        );
+        assert!(parse_address(&"1".repeat(65)).is_err());
+        assert!(parse_address("0xzz").is_err());
+    }
+}
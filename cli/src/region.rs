@@ -0,0 +1,563 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::{EnftError, EnftResult};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::Path;
+
+// This represents the default chunks of 100x100 that the image is divided into.
+pub const CHUNK_SIZE: u32 = 100;
+
+/// The largest bounding box of polygons, in pixels, that [Region::pixel_count] scans.
+const MAX_POLYGON_SCAN: usize = 1 << 30;
+
+/// The size of the chunks that the image is divided into. Chunks in the last
+/// row and column are cut off at the image border.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkGeometry {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for ChunkGeometry {
+    fn default() -> Self {
+        ChunkGeometry {
+            width: CHUNK_SIZE,
+            height: CHUNK_SIZE,
+        }
+    }
+}
+
+impl ChunkGeometry {
+    /// The number of (rows, columns) of chunks covering an image, including
+    /// partial chunks at the edges.
+    pub fn grid(&self, image_width: usize, image_height: usize) -> (usize, usize) {
+        (
+            image_height.div_ceil(self.height as usize),
+            image_width.div_ceil(self.width as usize),
+        )
+    }
+}
+
+/// The part of the image that is obfuscated and encrypted. This is recorded in
+/// the ciphertext, and the selected pixel values are encrypted in the order
+/// given by [Region::pixel_indices].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Region {
+    /// Chunks as (row, column) indices, chosen by the described sampling strategy.
+    Chunks {
+        geometry: ChunkGeometry,
+        indices: Vec<(usize, usize)>,
+        strategy: String,
+    },
+    /// Runs of (first pixel index, length) in row-major order, e.g. from a mask image.
+    Mask(Vec<(u64, u64)>),
+    /// Rectangles and polygons in pixel coordinates.
+    Shapes(RegionSpec),
+}
+
+/// A user supplied region spec, read from JSON, e.g.
+/// `{"rectangles": [{"x": 10, "y": 20, "width": 50, "height": 40}], "polygons": [[[0, 0], [10, 0], [5, 8]]]}`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegionSpec {
+    #[serde(default)]
+    pub rectangles: Vec<Rectangle>,
+    /// Each polygon is a list of (x, y) vertices. A pixel is covered if its
+    /// center lies inside the polygon (even-odd rule).
+    #[serde(default)]
+    pub polygons: Vec<Vec<(u32, u32)>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rectangle {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    /// The indices (into the row-major pixel array) of the selected pixels, in
+    /// the order their values are encrypted.
+    pub fn pixel_indices(&self, width: usize, height: usize) -> EnftResult<Vec<usize>> {
+        match self {
+            Region::Chunks {
+                geometry, indices, ..
+            } => {
+                let (chunk_width, chunk_height) =
+                    (geometry.width as usize, geometry.height as usize);
+                if chunk_width == 0 || chunk_height == 0 {
+                    return Err(EnftError::InvalidRegion(
+                        "Chunk dimensions must be positive".to_string(),
+                    ));
+                }
+                let mut pixels = vec![];
+                for (i, j) in indices.iter() {
+                    let (rows, columns) =
+                        chunk_bounds((*i, *j), (chunk_width, chunk_height), width, height)?;
+                    for ii in rows {
+                        for jj in columns.clone() {
+                            pixels.push(ii * width + jj);
+                        }
+                    }
+                }
+                Ok(pixels)
+            }
+            Region::Mask(runs) => {
+                let image_pixels = width.checked_mul(height).ok_or(EnftError::ImageMismatch)?;
+                let mut pixels = vec![];
+                for (start, len) in runs.iter() {
+                    pixels.extend(mask_run(*start, *len, image_pixels)?);
+                }
+                Ok(pixels)
+            }
+            Region::Shapes(spec) => {
+                spec.validate()?;
+                let image_pixels = width.checked_mul(height).ok_or(EnftError::ImageMismatch)?;
+                Ok((0..image_pixels)
+                    .filter(|idx| spec.covers(idx % width, idx / width))
+                    .collect())
+            }
+        }
+    }
+
+    /// The number of pixels selected by [Region::pixel_indices], computed without
+    /// listing them, so that untrusted dimensions cannot exhaust memory or overflow.
+    pub fn pixel_count(&self, width: usize, height: usize) -> EnftResult<usize> {
+        let too_large = || EnftError::InvalidRegion("The region is too large".to_string());
+        match self {
+            Region::Chunks {
+                geometry, indices, ..
+            } => {
+                let (chunk_width, chunk_height) =
+                    (geometry.width as usize, geometry.height as usize);
+                if chunk_width == 0 || chunk_height == 0 {
+                    return Err(EnftError::InvalidRegion(
+                        "Chunk dimensions must be positive".to_string(),
+                    ));
+                }
+                indices.iter().try_fold(0usize, |count, (i, j)| {
+                    let (rows, columns) =
+                        chunk_bounds((*i, *j), (chunk_width, chunk_height), width, height)?;
+                    rows.len()
+                        .checked_mul(columns.len())
+                        .and_then(|pixels| count.checked_add(pixels))
+                        .ok_or_else(too_large)
+                })
+            }
+            Region::Mask(runs) => {
+                let pixels = width.checked_mul(height).ok_or(EnftError::ImageMismatch)?;
+                runs.iter().try_fold(0usize, |count, (start, len)| {
+                    let run = mask_run(*start, *len, pixels)?;
+                    count.checked_add(run.len()).ok_or_else(too_large)
+                })
+            }
+            Region::Shapes(spec) => {
+                spec.validate()?;
+                let rectangles: Vec<_> = spec
+                    .rectangles
+                    .iter()
+                    .map(|rect| rect.clip(width, height))
+                    .collect();
+                let count = rectangles_area(&rectangles).ok_or_else(too_large)?;
+
+                // Polygons are scanned pixel by pixel in their bounding box, clipped to
+                // the image, skipping the pixels counted with the rectangles.
+                let (mut xs, mut ys) = ((usize::MAX, 0), (usize::MAX, 0));
+                for &(x, y) in spec.polygons.iter().flatten() {
+                    xs = (xs.0.min(x as usize), xs.1.max(x as usize));
+                    ys = (ys.0.min(y as usize), ys.1.max(y as usize));
+                }
+                let (columns, rows) = (xs.0..xs.1.min(width), ys.0..ys.1.min(height));
+                match columns.len().checked_mul(rows.len()) {
+                    Some(area) if area <= MAX_POLYGON_SCAN => {}
+                    _ => return Err(too_large()),
+                }
+                let mut polygon_count = 0usize;
+                for y in rows {
+                    for x in columns.clone() {
+                        if !rectangles
+                            .iter()
+                            .any(|(columns, rows)| columns.contains(&x) && rows.contains(&y))
+                            && spec
+                                .polygons
+                                .iter()
+                                .any(|polygon| polygon_covers(polygon, x, y))
+                        {
+                            polygon_count += 1;
+                        }
+                    }
+                }
+                count.checked_add(polygon_count).ok_or_else(too_large)
+            }
+        }
+    }
+}
+
+impl Rectangle {
+    /// The (columns, rows) of the rectangle, cut off at the image border.
+    fn clip(&self, width: usize, height: usize) -> (Range<usize>, Range<usize>) {
+        let (x, y) = (self.x as usize, self.y as usize);
+        (
+            x.min(width)..x.saturating_add(self.width as usize).min(width),
+            y.min(height)..y.saturating_add(self.height as usize).min(height),
+        )
+    }
+}
+
+impl RegionSpec {
+    /// Read a region spec from a JSON file.
+    pub fn from_json_file(path: impl AsRef<Path>) -> EnftResult<Self> {
+        let spec: RegionSpec = serde_json::from_slice(&std::fs::read(path)?)
+            .map_err(|e| EnftError::InvalidRegion(e.to_string()))?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    fn validate(&self) -> EnftResult<()> {
+        if self.polygons.iter().any(|polygon| polygon.len() < 3) {
+            return Err(EnftError::InvalidRegion(
+                "A polygon needs at least 3 vertices".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn covers(&self, x: usize, y: usize) -> bool {
+        self.rectangles.iter().any(|rect| {
+            let (rx, ry) = (rect.x as usize, rect.y as usize);
+            rx <= x
+                && x < rx.saturating_add(rect.width as usize)
+                && ry <= y
+                && y < ry.saturating_add(rect.height as usize)
+        }) || self
+            .polygons
+            .iter()
+            .any(|polygon| polygon_covers(polygon, x, y))
+    }
+}
+
+/// The number of pixels covered by any of the clipped (columns, rows) rectangles.
+/// The rows are grouped into bands between the rectangle edges, so large rectangles
+/// are not scanned row by row. None on overflow.
+fn rectangles_area(rectangles: &[(Range<usize>, Range<usize>)]) -> Option<usize> {
+    let mut edges: Vec<usize> = rectangles
+        .iter()
+        .flat_map(|(_, rows)| [rows.start, rows.end])
+        .collect();
+    edges.sort_unstable();
+    edges.dedup();
+    let mut area = 0usize;
+    for band in edges.windows(2) {
+        let (top, bottom) = (band[0], band[1]);
+        let mut spans: Vec<_> = rectangles
+            .iter()
+            .filter(|(_, rows)| rows.start <= top && bottom <= rows.end)
+            .map(|(columns, _)| columns.clone())
+            .collect();
+        spans.sort_unstable_by_key(|columns| columns.start);
+        let (mut covered, mut end) = (0usize, 0);
+        for columns in spans {
+            let start = columns.start.max(end);
+            if columns.end > start {
+                covered += columns.end - start;
+                end = columns.end;
+            }
+        }
+        area = area.checked_add(covered.checked_mul(bottom - top)?)?;
+    }
+    Some(area)
+}
+
+/// The rows and columns of the chunk at (row, column) `index`, cut off at the image
+/// border. The index comes from an untrusted ciphertext, so the bounds must not overflow.
+fn chunk_bounds(
+    (i, j): (usize, usize),
+    (chunk_width, chunk_height): (usize, usize),
+    width: usize,
+    height: usize,
+) -> EnftResult<(Range<usize>, Range<usize>)> {
+    match (i.checked_mul(chunk_height), j.checked_mul(chunk_width)) {
+        (Some(top), Some(left)) if top < height && left < width => Ok((
+            top..top + chunk_height.min(height - top),
+            left..left + chunk_width.min(width - left),
+        )),
+        _ => Err(EnftError::ImageMismatch),
+    }
+}
+
+/// The pixel indices of a mask run, if it lies within an image of `pixels` pixels.
+/// The run comes from an untrusted ciphertext, so its bounds must not overflow.
+fn mask_run(start: u64, len: u64, pixels: usize) -> EnftResult<Range<usize>> {
+    let start = usize::try_from(start).map_err(|_| EnftError::ImageMismatch)?;
+    match usize::try_from(len)
+        .ok()
+        .and_then(|len| start.checked_add(len))
+    {
+        Some(end) if end <= pixels => Ok(start..end),
+        _ => Err(EnftError::ImageMismatch),
+    }
+}
+
+/// Whether the center of pixel (x, y) lies inside the polygon. All coordinates are
+/// doubled so the pixel center is an odd integer while the vertices are even, which
+/// keeps the test exact and the center never on a vertex row. Doubled vertices take
+/// up to 33 bits, so the products below are computed in i128.
+fn polygon_covers(polygon: &[(u32, u32)], x: usize, y: usize) -> bool {
+    let (px, py) = (2 * x as i128 + 1, 2 * y as i128 + 1);
+    let mut inside = false;
+    for (k, &(xi, yi)) in polygon.iter().enumerate() {
+        let (xj, yj) = polygon[(k + 1) % polygon.len()];
+        let (xi, yi, xj, yj) = (
+            2 * xi as i128,
+            2 * yi as i128,
+            2 * xj as i128,
+            2 * yj as i128,
+        );
+        if (yi > py) != (yj > py) {
+            // px < xi + (xj - xi) * (py - yi) / (yj - yi), without the division.
+            let lhs = (px - xi) * (yj - yi);
+            let rhs = (xj - xi) * (py - yi);
+            if (yj > yi && lhs < rhs) || (yj < yi && lhs > rhs) {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Build a region from a mask image of the same dimensions as the NFT. Pixels
+/// that are neither black nor fully transparent are selected, however dark their
+/// color is.
+pub fn mask_from_image(path: impl AsRef<Path>, width: u32, height: u32) -> EnftResult<Region> {
+    let mask = image::open(path)?.to_rgba8();
+    if mask.dimensions() != (width, height) {
+        return Err(EnftError::InvalidRegion(format!(
+            "Mask is {}x{} but the image is {}x{}",
+            mask.width(),
+            mask.height(),
+            width,
+            height
+        )));
+    }
+    let mut runs: Vec<(u64, u64)> = vec![];
+    for (idx, pixel) in mask.pixels().enumerate() {
+        let [r, g, b, a] = pixel.0;
+        if (r == 0 && g == 0 && b == 0) || a == 0 {
+            continue;
+        }
+        match runs.last_mut() {
+            Some((start, len)) if *start + *len == idx as u64 => *len += 1,
+            _ => runs.push((idx as u64, 1)),
+        }
+    }
+    Ok(Region::Mask(runs))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_shapes() {
+        let spec = RegionSpec {
+            rectangles: vec![Rectangle {
+                x: 1,
+                y: 1,
+                width: 2,
+                height: 1,
+            }],
+            // A right triangle covering the centers of (0, 3), (0, 4) and (1, 4).
+            polygons: vec![vec![(0, 2), (4, 6), (0, 6)]],
+        };
+        assert_eq!(
+            Region::Shapes(spec).pixel_indices(4, 5).unwrap(),
+            vec![5, 6, 12, 16, 17]
+        );
+
+        // Far-out vertices and rectangles do not overflow. The triangle covers the
+        // pixels on and above the diagonal.
+        let spec = RegionSpec {
+            rectangles: vec![Rectangle {
+                x: u32::MAX,
+                y: u32::MAX,
+                width: u32::MAX,
+                height: u32::MAX,
+            }],
+            polygons: vec![vec![(u32::MAX, 0), (u32::MAX, u32::MAX), (0, 0)]],
+        };
+        assert_eq!(
+            Region::Shapes(spec).pixel_indices(4, 5).unwrap(),
+            vec![0, 1, 2, 3, 5, 6, 7, 10, 11, 15]
+        );
+    }
+
+    #[test]
+    fn test_mask_runs_out_of_range() {
+        for runs in [vec![(u64::MAX, 1)], vec![(0, u64::MAX)], vec![(18, 3)]] {
+            assert!(matches!(
+                Region::Mask(runs).pixel_indices(4, 5),
+                Err(EnftError::ImageMismatch)
+            ));
+        }
+        assert_eq!(
+            Region::Mask(vec![(18, 2)]).pixel_indices(4, 5).unwrap(),
+            vec![18, 19]
+        );
+    }
+
+    #[test]
+    fn test_partial_chunks() {
+        let region = Region::Chunks {
+            geometry: ChunkGeometry::default(),
+            indices: vec![(0, 1)],
+            strategy: "count:1".to_string(),
+        };
+        assert_eq!(region.pixel_indices(200, 100).unwrap().len(), 100 * 100);
+        // The chunk is cut off at the right border.
+        assert_eq!(region.pixel_indices(150, 100).unwrap().len(), 50 * 100);
+        assert!(region.pixel_indices(100, 100).is_err());
+        let huge = Region::Chunks {
+            geometry: ChunkGeometry {
+                width: u32::MAX,
+                height: u32::MAX,
+            },
+            indices: vec![(usize::MAX, 0), (0, usize::MAX)],
+            strategy: "count:2".to_string(),
+        };
+        assert!(matches!(
+            huge.pixel_indices(200, 100),
+            Err(EnftError::ImageMismatch)
+        ));
+        assert_eq!(region.pixel_count(150, 100).unwrap(), 50 * 100);
+        assert!(region.pixel_count(100, 100).is_err());
+
+        // A small image is still covered by a single partial chunk.
+        let geometry = ChunkGeometry {
+            width: 16,
+            height: 8,
+        };
+        assert_eq!(geometry.grid(40, 20), (3, 3));
+        let region = Region::Chunks {
+            geometry,
+            indices: vec![(2, 2)],
+            strategy: "count:1".to_string(),
+        };
+        assert_eq!(
+            region.pixel_indices(40, 20).unwrap(),
+            (16..20)
+                .flat_map(|y| (32..40).map(move |x| y * 40 + x))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(region.pixel_count(40, 20).unwrap(), 4 * 8);
+    }
+
+    #[test]
+    fn test_pixel_count() {
+        let spec = RegionSpec {
+            rectangles: vec![Rectangle {
+                x: 1,
+                y: 1,
+                width: 2,
+                height: 1,
+            }],
+            polygons: vec![vec![(0, 2), (4, 6), (0, 6)]],
+        };
+        // Overlapping shapes are counted once.
+        let overlapping = RegionSpec {
+            rectangles: vec![
+                Rectangle {
+                    x: 0,
+                    y: 0,
+                    width: 3,
+                    height: 3,
+                },
+                Rectangle {
+                    x: 2,
+                    y: 1,
+                    width: u32::MAX,
+                    height: 2,
+                },
+            ],
+            polygons: vec![vec![(1, 1), (4, 1), (1, u32::MAX)]],
+        };
+        let regions = [
+            Region::Shapes(spec),
+            Region::Shapes(overlapping),
+            Region::Mask(vec![(0, 3), (10, 5)]),
+            Region::Chunks {
+                geometry: ChunkGeometry {
+                    width: 3,
+                    height: 2,
+                },
+                indices: vec![(0, 0), (1, 1), (2, 1)],
+                strategy: "count:3".to_string(),
+            },
+        ];
+        for region in &regions {
+            assert_eq!(
+                region.pixel_count(4, 5).unwrap(),
+                region.pixel_indices(4, 5).unwrap().len()
+            );
+        }
+
+        // Huge regions and dimensions are errors, not overflows or allocations.
+        let huge = Region::Mask(vec![(0, u64::MAX), (u64::MAX, 1)]);
+        assert!(huge.pixel_count(usize::MAX, usize::MAX).is_err());
+        assert!(huge.pixel_count(4, 5).is_err());
+        let huge = Region::Chunks {
+            geometry: ChunkGeometry {
+                width: u32::MAX,
+                height: u32::MAX,
+            },
+            indices: vec![(0, 0), (usize::MAX, 0)],
+            strategy: "count:2".to_string(),
+        };
+        assert!(huge.pixel_count(usize::MAX, usize::MAX).is_err());
+
+        // Rectangles are counted without scanning them, polygons are scanned only
+        // up to a bound.
+        let max = u32::MAX as usize;
+        let rectangle = Rectangle {
+            x: 0,
+            y: 0,
+            width: u32::MAX,
+            height: u32::MAX,
+        };
+        let huge = Region::Shapes(RegionSpec {
+            rectangles: vec![rectangle.clone(), rectangle],
+            polygons: vec![],
+        });
+        assert_eq!(huge.pixel_count(max, max).unwrap(), max * max);
+        let huge = Region::Shapes(RegionSpec {
+            rectangles: vec![],
+            polygons: vec![vec![(0, 0), (u32::MAX, 0), (0, u32::MAX)]],
+        });
+        assert!(matches!(
+            huge.pixel_count(max, max),
+            Err(EnftError::InvalidRegion(_))
+        ));
+        assert_eq!(
+            huge.pixel_count(4, 5).unwrap(),
+            huge.pixel_indices(4, 5).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_mask_from_image() {
+        let mut mask = image::RgbaImage::new(4, 1);
+        mask.put_pixel(0, 0, image::Rgba([0, 0, 1, 255]));
+        mask.put_pixel(1, 0, image::Rgba([0, 0, 0, 255]));
+        mask.put_pixel(2, 0, image::Rgba([255, 255, 255, 0]));
+        mask.put_pixel(3, 0, image::Rgba([1, 0, 0, 1]));
+        let path = std::env::temp_dir().join(format!("enft-mask-{}.png", rand::random::<u64>()));
+        mask.save(&path).unwrap();
+
+        // Dark colors are selected, black and fully transparent pixels are not.
+        let region = mask_from_image(&path, 4, 1).unwrap();
+        assert!(matches!(region, Region::Mask(runs) if runs == [(0, 1), (3, 1)]));
+        assert!(mask_from_image(&path, 4, 2).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use fastcrypto::hash::{Blake2b256, HashFunction};
+use rand::seq::index;
+use std::fmt;
+use std::str::FromStr;
+
+/// A strategy for choosing which chunks of the image are obfuscated (and
+/// encrypted). The image is divided into a grid of `rows` x `cols` chunks.
+pub trait SamplingStrategy {
+    /// Select chunks as (row, column) indices into the grid.
+    fn select(&self, rows: usize, cols: usize) -> Vec<(usize, usize)>;
+
+    /// A description of the strategy and its parameters. This is recorded in
+    /// the ciphertext so the listing can be audited.
+    fn describe(&self) -> String;
+}
+
+/// The built-in sampling strategies, selected with `encrypt --strategy`. They
+/// are parsed from and described as:
+/// - `count:<n>`: `n` chunks chosen uniformly at random.
+/// - `percent:<p>`: `p` percent of the chunks (at least one) chosen uniformly at random.
+/// - `checkerboard`: every other chunk, starting with the top left one.
+/// - `seeded:<seed>:<n>`: `n` chunks chosen deterministically from `seed`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuiltinStrategy {
+    UniformCount { count: usize },
+    Percentage { percent: u8 },
+    Checkerboard,
+    Seeded { seed: u64, count: usize },
+}
+
+impl Default for BuiltinStrategy {
+    /// The same expected density as the original 1-in-5 selection, but never empty.
+    fn default() -> Self {
+        BuiltinStrategy::Percentage { percent: 20 }
+    }
+}
+
+impl SamplingStrategy for BuiltinStrategy {
+    fn select(&self, rows: usize, cols: usize) -> Vec<(usize, usize)> {
+        let total = rows * cols;
+        let mut selected = match self {
+            BuiltinStrategy::UniformCount { count } => {
+                index::sample(&mut rand::thread_rng(), total, (*count).min(total)).into_vec()
+            }
+            BuiltinStrategy::Percentage { percent } => {
+                let count = (total * *percent as usize).div_ceil(100);
+                index::sample(&mut rand::thread_rng(), total, count.max(1).min(total)).into_vec()
+            }
+            BuiltinStrategy::Checkerboard => (0..total)
+                .filter(|idx| (idx / cols + idx % cols) % 2 == 0)
+                .collect(),
+            BuiltinStrategy::Seeded { seed, count } => {
+                // Rank the chunks by a hash of the seed and the chunk index, so the
+                // selection does not depend on the algorithms of the rand crate.
+                let mut ranked: Vec<(usize, [u8; 32])> = (0..total)
+                    .map(|idx| {
+                        let mut hash = Blake2b256::new();
+                        hash.update(seed.to_be_bytes());
+                        hash.update((idx as u64).to_be_bytes());
+                        (idx, hash.finalize().digest)
+                    })
+                    .collect();
+                ranked.sort_by(|a, b| a.1.cmp(&b.1));
+                ranked
+                    .into_iter()
+                    .take(*count)
+                    .map(|(idx, _)| idx)
+                    .collect()
+            }
+        };
+        selected.sort_unstable();
+        selected
+            .into_iter()
+            .map(|idx| (idx / cols, idx % cols))
+            .collect()
+    }
+
+    fn describe(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for BuiltinStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuiltinStrategy::UniformCount { count } => write!(f, "count:{}", count),
+            BuiltinStrategy::Percentage { percent } => write!(f, "percent:{}", percent),
+            BuiltinStrategy::Checkerboard => write!(f, "checkerboard"),
+            BuiltinStrategy::Seeded { seed, count } => write!(f, "seeded:{}:{}", seed, count),
+        }
+    }
+}
+
+impl FromStr for BuiltinStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let parse_count = |count: &str| match count.parse::<usize>() {
+            Ok(count) if count > 0 => Ok(count),
+            _ => Err(format!("Invalid chunk count: {}", count)),
+        };
+        match parts.as_slice() {
+            ["count", count] => Ok(BuiltinStrategy::UniformCount {
+                count: parse_count(count)?,
+            }),
+            ["percent", percent] => match percent.parse::<u8>() {
+                Ok(percent) if (1..=100).contains(&percent) => {
+                    Ok(BuiltinStrategy::Percentage { percent })
+                }
+                _ => Err(format!("Invalid percentage: {}", percent)),
+            },
+            ["checkerboard"] => Ok(BuiltinStrategy::Checkerboard),
+            ["seeded", seed, count] => Ok(BuiltinStrategy::Seeded {
+                seed: seed
+                    .parse()
+                    .map_err(|_| format!("Invalid seed: {}", seed))?,
+                count: parse_count(count)?,
+            }),
+            _ => Err(format!(
+                "Unknown strategy '{}', expected one of count:<n>, percent:<p>, checkerboard, seeded:<seed>:<n>",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_builtin_strategies() {
+        let count = BuiltinStrategy::UniformCount { count: 7 }.select(4, 5);
+        assert_eq!(count.len(), 7);
+        assert!(count.iter().all(|(i, j)| *i < 4 && *j < 5));
+
+        // 1% of 20 chunks rounds up to one chunk rather than none.
+        assert_eq!(
+            BuiltinStrategy::Percentage { percent: 1 }
+                .select(4, 5)
+                .len(),
+            1
+        );
+
+        assert_eq!(
+            BuiltinStrategy::Checkerboard.select(2, 3),
+            vec![(0, 0), (0, 2), (1, 1)]
+        );
+
+        let seeded = BuiltinStrategy::Seeded { seed: 42, count: 6 };
+        assert_eq!(seeded.select(10, 10), seeded.select(10, 10));
+        assert_eq!(seeded.select(10, 10).len(), 6);
+    }
+
+    #[test]
+    fn test_parse_and_describe() {
+        for spec in ["count:3", "percent:20", "checkerboard", "seeded:7:12"] {
+            let strategy: BuiltinStrategy = spec.parse().unwrap();
+            assert_eq!(strategy.describe(), spec);
+        }
+        assert!("count:0".parse::<BuiltinStrategy>().is_err());
+        assert!("percent:101".parse::<BuiltinStrategy>().is_err());
+        assert!("random".parse::<BuiltinStrategy>().is_err());
+    }
+}
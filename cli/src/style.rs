@@ -0,0 +1,204 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::utils::LoadedImage;
+use fastcrypto::encoding::{Encoding, Hex};
+use image::{imageops, RgbaImage};
+use rand::Rng;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// How the obfuscated pixels look in the public preview. This only changes the
+/// obfuscated image; the original pixel values are encrypted regardless of the
+/// style and restored exactly on decryption. They are parsed from and described as:
+/// - `transparent`: all four bytes set to zero (the original behavior).
+/// - `solid:<rrggbb>`: a solid color, keeping the original alpha.
+/// - `pixelate:<block>`: the mean color of each `block` x `block` square of the original,
+///   with `block` at least [MIN_PIXELATE_BLOCK].
+/// - `blur:<sigma>`: a Gaussian blur of the original, with `sigma` at least [MIN_BLUR_SIGMA].
+/// - `noise`: random colors.
+/// - `mean`: the mean color of all obfuscated pixels.
+///
+/// `pixelate`, `blur` and `mean` are computed from the original pixels, so the preview
+/// leaks their low-frequency content (coarse shapes and colors). Use `transparent`,
+/// `solid` or `noise` if nothing about the hidden pixels may be public.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ObfuscationStyle {
+    #[default]
+    Transparent,
+    Solid([u8; 3]),
+    Pixelate(u32),
+    Blur(f32),
+    Noise,
+    Mean,
+}
+
+/// The smallest block size of `pixelate`. A 1x1 block would show the original pixels.
+pub const MIN_PIXELATE_BLOCK: u32 = 2;
+
+/// The smallest sigma of `blur`. Smaller sigmas leave the original pixels nearly intact.
+pub const MIN_BLUR_SIGMA: f32 = 1.0;
+
+impl ObfuscationStyle {
+    /// Restyle the given pixels of the obfuscated image, which starts out as a copy of
+    /// the original image bytes (with the width and height prefix).
+    pub fn apply(&self, image: &LoadedImage, pixel_indices: &[usize], obfuscated_image: &mut [u8]) {
+        let width = image.width as usize;
+        let original = &image.data[8..];
+        let obfuscated = &mut obfuscated_image[8..];
+        let set_rgb = |obfuscated: &mut [u8], idx: usize, rgb: &[u8]| {
+            obfuscated[4 * idx..4 * idx + 3].copy_from_slice(rgb)
+        };
+
+        match self {
+            ObfuscationStyle::Transparent => {
+                for &idx in pixel_indices {
+                    obfuscated[4 * idx..4 * idx + 4].fill(0);
+                }
+            }
+            ObfuscationStyle::Solid(rgb) => {
+                for &idx in pixel_indices {
+                    set_rgb(obfuscated, idx, rgb);
+                }
+            }
+            ObfuscationStyle::Pixelate(block) => {
+                let block = *block as usize;
+                let height = image.height as usize;
+                let mut block_means: HashMap<(usize, usize), [u8; 3]> = HashMap::new();
+                for &idx in pixel_indices {
+                    let (bx, by) = ((idx % width) / block * block, (idx / width) / block * block);
+                    let mean = *block_means.entry((bx, by)).or_insert_with(|| {
+                        mean_rgb(
+                            original,
+                            (by..(by + block).min(height)).flat_map(|y| {
+                                (bx..(bx + block).min(width)).map(move |x| y * width + x)
+                            }),
+                        )
+                    });
+                    set_rgb(obfuscated, idx, &mean);
+                }
+            }
+            ObfuscationStyle::Blur(sigma) => {
+                let buffer = RgbaImage::from_raw(image.width, image.height, original.to_vec())
+                    .expect("image data matches its dimensions");
+                let blurred = imageops::blur(&buffer, *sigma).into_raw();
+                for &idx in pixel_indices {
+                    set_rgb(obfuscated, idx, &blurred[4 * idx..4 * idx + 3]);
+                }
+            }
+            ObfuscationStyle::Noise => {
+                let mut rng = rand::thread_rng();
+                for &idx in pixel_indices {
+                    set_rgb(obfuscated, idx, &rng.gen::<[u8; 3]>());
+                }
+            }
+            ObfuscationStyle::Mean => {
+                let mean = mean_rgb(original, pixel_indices.iter().copied());
+                for &idx in pixel_indices {
+                    set_rgb(obfuscated, idx, &mean);
+                }
+            }
+        }
+    }
+}
+
+/// The mean color of the given pixels of an RGBA buffer.
+fn mean_rgb(pixels: &[u8], indices: impl Iterator<Item = usize>) -> [u8; 3] {
+    let mut sum = [0u64; 3];
+    let mut count = 0u64;
+    for idx in indices {
+        for (c, total) in sum.iter_mut().enumerate() {
+            *total += pixels[4 * idx + c] as u64;
+        }
+        count += 1;
+    }
+    sum.map(|total| (total / count.max(1)) as u8)
+}
+
+impl fmt::Display for ObfuscationStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObfuscationStyle::Transparent => write!(f, "transparent"),
+            ObfuscationStyle::Solid(rgb) => write!(f, "solid:{}", Hex::encode(rgb)),
+            ObfuscationStyle::Pixelate(block) => write!(f, "pixelate:{}", block),
+            ObfuscationStyle::Blur(sigma) => write!(f, "blur:{}", sigma),
+            ObfuscationStyle::Noise => write!(f, "noise"),
+            ObfuscationStyle::Mean => write!(f, "mean"),
+        }
+    }
+}
+
+impl FromStr for ObfuscationStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        match parts.as_slice() {
+            ["transparent"] => Ok(ObfuscationStyle::Transparent),
+            ["solid", rgb] => Hex::decode(rgb)
+                .ok()
+                .and_then(|rgb| rgb.try_into().ok())
+                .map(ObfuscationStyle::Solid)
+                .ok_or_else(|| format!("Invalid color, expected rrggbb: {}", rgb)),
+            ["pixelate", block] => match block.parse::<u32>() {
+                Ok(block) if block >= MIN_PIXELATE_BLOCK => Ok(ObfuscationStyle::Pixelate(block)),
+                _ => Err(format!(
+                    "Invalid block size {}, expected at least {}",
+                    block, MIN_PIXELATE_BLOCK
+                )),
+            },
+            ["blur", sigma] => match sigma.parse::<f32>() {
+                Ok(sigma) if sigma >= MIN_BLUR_SIGMA && sigma.is_finite() => {
+                    Ok(ObfuscationStyle::Blur(sigma))
+                }
+                _ => Err(format!(
+                    "Invalid blur sigma {}, expected at least {}",
+                    sigma, MIN_BLUR_SIGMA
+                )),
+            },
+            ["noise"] => Ok(ObfuscationStyle::Noise),
+            ["mean"] => Ok(ObfuscationStyle::Mean),
+            _ => Err(format!(
+                "Unknown style '{}', expected one of transparent, solid:<rrggbb>, pixelate:<block>, blur:<sigma>, noise, mean",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_styles() {
+        for style in [
+            "transparent",
+            "solid:ff8000",
+            "pixelate:2",
+            "blur:1",
+            "noise",
+            "mean",
+        ] {
+            assert_eq!(
+                style.parse::<ObfuscationStyle>().unwrap().to_string(),
+                style
+            );
+        }
+        // Styles that would show the hidden pixels (nearly) in the clear are rejected.
+        for style in [
+            "pixelate:0",
+            "pixelate:1",
+            "blur:0",
+            "blur:0.01",
+            "blur:0.99",
+            "blur:inf",
+            "blur:NaN",
+            "solid:ff80",
+            "sparkles",
+        ] {
+            assert!(style.parse::<ObfuscationStyle>().is_err(), "{}", style);
+        }
+    }
+}
@@ -0,0 +1,323 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::{EnftError, EnftResult};
+use crate::utils::{parse_g1_element, parse_scalar};
+use fastcrypto::encoding::{Encoding, Hex};
+use fastcrypto::groups::bls12381::{reduce_mod_uniform_buffer, G1Element, Scalar};
+use fastcrypto::groups::{GroupElement, Scalar as _};
+use fastcrypto::hash::{HashFunction, Sha512};
+use fastcrypto::serde_helpers::ToFromByteArray;
+use fastcrypto::traits::AllowedRng;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The share of the master secret held by one of n key servers. In threshold mode
+/// the key of a user is `master_secret * user_scalar(app_id, user_id)`, so a server
+/// holding a share of the master secret holds a share of every user key. Co-owners
+/// hold a share of their joint key after a [crate::dkg]. Indices start at 1.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "KeyShareJson", into = "KeyShareJson")]
+pub struct KeyShare {
+    pub index: u16,
+    pub share: Scalar,
+}
+
+/// The public parameters of a Shamir-shared master secret: any `threshold` of the
+/// shares recover it. `verification_keys[i - 1]` is `share_i * G`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "ThresholdPublicKeyJson", into = "ThresholdPublicKeyJson")]
+pub struct ThresholdPublicKey {
+    pub threshold: u16,
+    pub master_pk: G1Element,
+    pub verification_keys: Vec<G1Element>,
+}
+
+/// Split a fresh master secret into `shares` shares of which any `threshold` recover
+/// it. The master secret itself is never returned.
+pub fn deal<R: AllowedRng>(
+    threshold: u16,
+    shares: u16,
+    rng: &mut R,
+) -> EnftResult<(Vec<KeyShare>, ThresholdPublicKey)> {
+    if threshold == 0 || threshold > shares {
+        return Err(EnftError::Threshold(format!(
+            "Invalid threshold {} of {} shares",
+            threshold, shares
+        )));
+    }
+    let coefficients = random_polynomial(threshold, rng);
+    let key_shares: Vec<KeyShare> = (1..=shares)
+        .map(|index| KeyShare {
+            index,
+            share: evaluate_polynomial(&coefficients, index),
+        })
+        .collect();
+
+    let gen = G1Element::generator();
+    let public_key = ThresholdPublicKey {
+        threshold,
+        master_pk: gen * coefficients[0],
+        verification_keys: key_shares.iter().map(|s| gen * s.share).collect(),
+    };
+    Ok((key_shares, public_key))
+}
+
+/// The coefficients of a random polynomial of degree `threshold - 1`.
+pub(crate) fn random_polynomial<R: AllowedRng>(threshold: u16, rng: &mut R) -> Vec<Scalar> {
+    (0..threshold).map(|_| Scalar::rand(rng)).collect()
+}
+
+/// Evaluate a polynomial at the index with Horner's method.
+pub(crate) fn evaluate_polynomial(coefficients: &[Scalar], index: u16) -> Scalar {
+    let x = Scalar::from(index as u128);
+    coefficients
+        .iter()
+        .rev()
+        .fold(Scalar::zero(), |acc, coefficient| acc * x + *coefficient)
+}
+
+/// Evaluate a polynomial committed to as `coefficient * G` at the index, giving
+/// `evaluate_polynomial(coefficients, index) * G`.
+pub(crate) fn evaluate_commitment(commitment: &[G1Element], index: u16) -> G1Element {
+    let x = Scalar::from(index as u128);
+    commitment
+        .iter()
+        .rev()
+        .fold(G1Element::zero(), |acc, coefficient| acc * x + *coefficient)
+}
+
+/// The scalar that the master secret is multiplied with to get the key of a user.
+pub fn user_scalar(app_id: &[u8], user_id: &[u8]) -> Scalar {
+    let mut hash = Sha512::new();
+    hash.update(b"enft-threshold-user");
+    hash.update((app_id.len() as u64).to_be_bytes());
+    hash.update(app_id);
+    hash.update(user_id);
+    reduce_mod_uniform_buffer(&hash.finalize().digest)
+}
+
+impl KeyShare {
+    /// This server's share of the key of a user.
+    pub fn user_key_share(&self, app_id: &[u8], user_id: &[u8]) -> Scalar {
+        self.share * user_scalar(app_id, user_id)
+    }
+
+    pub fn from_json_file(path: impl AsRef<Path>) -> EnftResult<Self> {
+        from_json_file(path)
+    }
+}
+
+impl ThresholdPublicKey {
+    /// The encryption pubkey of a user.
+    pub fn user_public_key(&self, app_id: &[u8], user_id: &[u8]) -> G1Element {
+        self.master_pk * user_scalar(app_id, user_id)
+    }
+
+    /// The pubkey of the share of a user key held by the server with the given index.
+    pub fn user_public_key_share(
+        &self,
+        index: u16,
+        app_id: &[u8],
+        user_id: &[u8],
+    ) -> EnftResult<G1Element> {
+        Ok(self.verification_key(index)? * user_scalar(app_id, user_id))
+    }
+
+    /// The verification key `share * G` of the share with the given index.
+    pub fn verification_key(&self, index: u16) -> EnftResult<G1Element> {
+        index
+            .checked_sub(1)
+            .and_then(|i| self.verification_keys.get(i as usize))
+            .copied()
+            .ok_or_else(|| EnftError::Threshold(format!("Unknown share index {}", index)))
+    }
+
+    pub fn from_json_file(path: impl AsRef<Path>) -> EnftResult<Self> {
+        from_json_file(path)
+    }
+}
+
+fn from_json_file<T: serde::de::DeserializeOwned>(path: impl AsRef<Path>) -> EnftResult<T> {
+    serde_json::from_slice(&std::fs::read(path)?).map_err(|e| EnftError::Threshold(e.to_string()))
+}
+
+/// The Lagrange coefficients for interpolating at zero from the given indices.
+pub fn lagrange_coefficients(indices: &[u16]) -> EnftResult<Vec<Scalar>> {
+    indices
+        .iter()
+        .map(|&i| {
+            let xi = Scalar::from(i as u128);
+            indices
+                .iter()
+                .filter(|&&j| j != i)
+                .try_fold(Scalar::generator(), |acc, &j| {
+                    let xj = Scalar::from(j as u128);
+                    let inverse = (xj - xi).inverse().map_err(|_| {
+                        EnftError::Threshold(format!("Duplicate share index {}", j))
+                    })?;
+                    Ok(acc * xj * inverse)
+                })
+        })
+        .collect()
+}
+
+/// Combine partial decryptions `eph * user_key_share_i`, given as (index, partial), into
+/// `eph * user_key` by Lagrange interpolation in the exponent. The partial decryptions
+/// must have been checked with their DLEQ proofs, and the first `threshold` are used.
+pub fn combine_partial_decryptions(
+    threshold: u16,
+    partials: &[(u16, G1Element)],
+) -> EnftResult<G1Element> {
+    let mut indices: Vec<u16> = partials.iter().map(|(index, _)| *index).collect();
+    indices.sort_unstable();
+    indices.dedup();
+    if indices.len() != partials.len() {
+        return Err(EnftError::Threshold(
+            "Duplicate partial decryptions".to_string(),
+        ));
+    }
+    if partials.len() < threshold as usize {
+        return Err(EnftError::Threshold(format!(
+            "Only {} of {} required partial decryptions",
+            partials.len(),
+            threshold
+        )));
+    }
+
+    let partials = &partials[..threshold as usize];
+    let indices: Vec<u16> = partials.iter().map(|(index, _)| *index).collect();
+    Ok(lagrange_coefficients(&indices)?
+        .into_iter()
+        .zip(partials)
+        .fold(G1Element::zero(), |acc, (lambda, (_, partial))| {
+            acc + *partial * lambda
+        }))
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct KeyShareJson {
+    index: u16,
+    share: String,
+}
+
+impl TryFrom<KeyShareJson> for KeyShare {
+    type Error = EnftError;
+
+    fn try_from(json: KeyShareJson) -> Result<Self, Self::Error> {
+        Ok(KeyShare {
+            index: json.index,
+            share: parse_scalar(&json.share)?,
+        })
+    }
+}
+
+impl From<KeyShare> for KeyShareJson {
+    fn from(share: KeyShare) -> Self {
+        KeyShareJson {
+            index: share.index,
+            share: Hex::encode(share.share.to_byte_array()),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct ThresholdPublicKeyJson {
+    threshold: u16,
+    master_pk: String,
+    verification_keys: Vec<String>,
+}
+
+impl TryFrom<ThresholdPublicKeyJson> for ThresholdPublicKey {
+    type Error = EnftError;
+
+    fn try_from(json: ThresholdPublicKeyJson) -> Result<Self, Self::Error> {
+        Ok(ThresholdPublicKey {
+            threshold: json.threshold,
+            master_pk: parse_g1_element(&json.master_pk)?,
+            verification_keys: json
+                .verification_keys
+                .iter()
+                .map(|key| parse_g1_element(key))
+                .collect::<EnftResult<_>>()?,
+        })
+    }
+}
+
+impl From<ThresholdPublicKey> for ThresholdPublicKeyJson {
+    fn from(public_key: ThresholdPublicKey) -> Self {
+        ThresholdPublicKeyJson {
+            threshold: public_key.threshold,
+            master_pk: Hex::encode(public_key.master_pk.to_byte_array()),
+            verification_keys: public_key
+                .verification_keys
+                .iter()
+                .map(|key| Hex::encode(key.to_byte_array()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elgamal;
+
+    #[test]
+    fn test_deal_and_combine() {
+        let mut rng = rand::thread_rng();
+        let (shares, public_key) = deal(3, 5, &mut rng).unwrap();
+        let (app_id, user_id) = (b"app".as_slice(), b"user".as_slice());
+        let pk = public_key.user_public_key(app_id, user_id);
+        let msk = G1Element::generator() * Scalar::rand(&mut rng);
+        let (enc, _) = elgamal::encrypt(&msk, &pk, &mut rng);
+
+        let partial = |share: &KeyShare| {
+            let sk_share = share.user_key_share(app_id, user_id);
+            let (partial, proof) =
+                elgamal::partial_decrypt(&enc.ephemeral, &sk_share, &mut rand::thread_rng());
+            let pk_share = public_key
+                .user_public_key_share(share.index, app_id, user_id)
+                .unwrap();
+            assert!(elgamal::verify_partial_decryption(
+                &pk_share,
+                &enc.ephemeral,
+                &partial,
+                &proof
+            )
+            .is_ok());
+            (share.index, partial)
+        };
+
+        // Any 3 of the 5 shares decrypt.
+        for subset in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let partials: Vec<_> = subset.iter().map(|&i| partial(&shares[i])).collect();
+            let combined = combine_partial_decryptions(3, &partials).unwrap();
+            assert_eq!(elgamal::decrypt_with_partial(&enc, &combined), msk);
+        }
+
+        // 2 shares are not enough, and a share cannot be counted twice.
+        let partials = vec![partial(&shares[0]), partial(&shares[1])];
+        assert!(combine_partial_decryptions(3, &partials).is_err());
+        let partials = vec![
+            partial(&shares[0]),
+            partial(&shares[1]),
+            partial(&shares[1]),
+        ];
+        assert!(combine_partial_decryptions(3, &partials).is_err());
+
+        assert!(deal(4, 3, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_json() {
+        let (shares, public_key) = deal(2, 3, &mut rand::thread_rng()).unwrap();
+        let json = serde_json::to_string(&shares[1]).unwrap();
+        assert_eq!(serde_json::from_str::<KeyShare>(&json).unwrap(), shares[1]);
+        let json = serde_json::to_string(&public_key).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ThresholdPublicKey>(&json).unwrap(),
+            public_key
+        );
+    }
+}
@@ -1,31 +1,67 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::blob::BlobEncoding;
+use crate::elgamal;
+use crate::elgamal::ElGamalEncryption;
+use crate::error::{EnftError, EnftResult};
+use crate::region::{ChunkGeometry, Region};
+use crate::sampling::SamplingStrategy;
+use crate::style::ObfuscationStyle;
 use crate::FullCipherText;
-use fastcrypto::aes::Cipher;
+use fastcrypto::encoding::{Encoding, Hex};
 use fastcrypto::hash::HashFunction;
 use fastcrypto::{
     aes::{Aes256Gcm, AesKey, GenericByteArray},
-    groups::bls12381::G1Element,
+    groups::bls12381::{G1Element, Scalar},
     hash::Blake2b256,
     serde_helpers::ToFromByteArray,
-    traits::Generate,
+    traits::{Generate, ToFromBytes},
 };
-use image::GenericImageView;
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use hkdf::Hkdf;
+use image::{DynamicImage, GenericImageView, ImageOutputFormat};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::de::DeserializeOwned;
+use sha2::Sha256;
+use std::io::{Read, Seek, Write};
+use std::path::Path;
 use typenum::U12;
 use typenum::U32;
 
-// This represents the chunks of 100x100 that the image is divided into.
-const CHUNK_SIZE: usize = 100;
-
 pub struct LoadedImage {
-    width: u32,
-    height: u32,
+    pub width: u32,
+    pub height: u32,
     pub data: Vec<u8>,
 }
-pub fn load_image(path: &str) -> LoadedImage {
-    let image = image::open(path).unwrap();
+
+impl LoadedImage {
+    /// An image from its RGBA pixels, e.g. from a canvas.
+    pub fn from_rgba(width: u32, height: u32, rgba: &[u8]) -> EnftResult<Self> {
+        let image = LoadedImage {
+            data: [&width.to_be_bytes()[..], &height.to_be_bytes()[..], rgba].concat(),
+            width,
+            height,
+        };
+        image_dimensions(&image.data)?;
+        Ok(image)
+    }
+
+    /// The RGBA pixels of the image.
+    pub fn rgba(&self) -> &[u8] {
+        &self.data[8..]
+    }
+}
+
+pub fn load_image(path: impl AsRef<Path>) -> EnftResult<LoadedImage> {
+    Ok(to_loaded_image(image::open(path)?))
+}
+
+/// Same as [load_image], but decodes the image from an in-memory buffer.
+pub fn load_image_from_memory(bytes: &[u8]) -> EnftResult<LoadedImage> {
+    Ok(to_loaded_image(image::load_from_memory(bytes)?))
+}
+
+fn to_loaded_image(image: DynamicImage) -> LoadedImage {
     let (width, height) = image.dimensions();
     LoadedImage {
         data: [
@@ -39,62 +75,132 @@ pub fn load_image(path: &str) -> LoadedImage {
     }
 }
 /// This reads in the image from the given path, and returns a PreprocessedImage struct,
-/// it has the selected pixel coordinates and its values and the obfuscated image (with
-/// the selected pixels removed).
-pub fn load_and_sample_image(nft_path: &str) -> PreprocessedImage {
-    let loaded_image = load_image(nft_path);
-    let mut obfuscated_image = loaded_image.data.clone();
+/// it has the selected region and its values and the obfuscated image (with the
+/// selected pixels removed). The chunks to obfuscate are chosen by the given strategy.
+pub fn load_and_sample_image(
+    nft_path: impl AsRef<Path>,
+    strategy: &dyn SamplingStrategy,
+    geometry: ChunkGeometry,
+    style: &ObfuscationStyle,
+) -> EnftResult<PreprocessedImage> {
+    sample_image(&load_image(nft_path)?, strategy, geometry, style)
+}
 
-    // a list of sampled coordinates
-    let mut selected_coordinates = vec![];
-
-    // a list of values in the sampled coordinates
-    let mut selected_values = vec![];
-
-    // this code can be customized!
-    // divvy up the image into chunks of 100x100 size and iterate through them.
-    for i in 0..loaded_image.height as usize / CHUNK_SIZE {
-        for j in 0..loaded_image.width as usize / CHUNK_SIZE {
-            // randomly select a 100x100 chunk
-            let x = rand::thread_rng().gen_range(0..5);
-            if x == 0 {
-                selected_coordinates.push((i, j));
-
-                // if selected, push all values in this chunk to the selected_values
-                for ii in i * CHUNK_SIZE..(i + 1) * CHUNK_SIZE {
-                    for jj in j * CHUNK_SIZE..(j + 1) * CHUNK_SIZE {
-                        let pixel_idx = ii * loaded_image.width as usize + jj;
-                        for k in 4 * pixel_idx + 8..4 * pixel_idx + 12 {
-                            selected_values.push(loaded_image.data[k]);
-                            obfuscated_image[k] = 0;
-                        }
-                    }
-                }
-            }
-        }
+/// Same as [load_and_sample_image], but for an image that is already loaded.
+pub fn sample_image(
+    loaded_image: &LoadedImage,
+    strategy: &dyn SamplingStrategy,
+    geometry: ChunkGeometry,
+    style: &ObfuscationStyle,
+) -> EnftResult<PreprocessedImage> {
+    // divvy up the image into chunks of the given size (including partial chunks at
+    // the edges) and let the strategy pick a list of sampled coordinates.
+    let (rows, cols) = geometry.grid(loaded_image.width as usize, loaded_image.height as usize);
+    let region = Region::Chunks {
+        geometry,
+        indices: strategy.select(rows, cols),
+        strategy: strategy.describe(),
+    };
+    obfuscate_image(loaded_image, region, style)
+}
+
+/// Remove the pixels in the given region from the image, and return them together
+/// with the obfuscated image where they are restyled with the given style. This is
+/// the common step for sampled chunks, masks and region specs.
+pub fn obfuscate_image(
+    loaded_image: &LoadedImage,
+    region: Region,
+    style: &ObfuscationStyle,
+) -> EnftResult<PreprocessedImage> {
+    let pixel_indices =
+        region.pixel_indices(loaded_image.width as usize, loaded_image.height as usize)?;
+
+    // a list of values in the selected region
+    let mut selected_values = Vec::with_capacity(4 * pixel_indices.len());
+    for pixel_idx in pixel_indices.iter() {
+        selected_values
+            .extend_from_slice(&loaded_image.data[4 * pixel_idx + 8..4 * pixel_idx + 12]);
     }
-    PreprocessedImage {
-        selected_coordinates,
+
+    let mut obfuscated_image = loaded_image.data.clone();
+    style.apply(loaded_image, &pixel_indices, &mut obfuscated_image);
+    Ok(PreprocessedImage {
+        region,
         selected_values,
         obfuscated_image,
-    }
+    })
 }
 
 pub struct PreprocessedImage {
-    pub selected_coordinates: Vec<(usize, usize)>,
+    pub region: Region,
     pub selected_values: Vec<u8>,
     pub obfuscated_image: Vec<u8>,
 }
 
 /// This takes the image bytes and save it as an image file.
-pub fn save_image(path: &str, image_bytes: &[u8]) {
-    let _ = image::save_buffer(
+pub fn save_image(path: impl AsRef<Path>, image_bytes: &[u8]) -> EnftResult<()> {
+    let (width, height) = image_dimensions(image_bytes)?;
+    image::save_buffer(
         path,
         &image_bytes[8..],
-        u32::from_be_bytes(image_bytes[0..4].try_into().unwrap()),
-        u32::from_be_bytes(image_bytes[4..8].try_into().unwrap()),
+        width,
+        height,
+        image::ColorType::Rgba8,
+    )?;
+    Ok(())
+}
+
+/// This takes the image bytes and writes them PNG encoded to the given writer.
+pub fn write_image<W: Write + Seek>(writer: &mut W, image_bytes: &[u8]) -> EnftResult<()> {
+    let (width, height) = image_dimensions(image_bytes)?;
+    image::write_buffer_with_format(
+        writer,
+        &image_bytes[8..],
+        width,
+        height,
         image::ColorType::Rgba8,
-    );
+        ImageOutputFormat::Png,
+    )?;
+    Ok(())
+}
+
+/// Write the ciphertext in the given encoding to the given writer.
+pub fn write_ciphertext<W: Write>(
+    writer: &mut W,
+    ciphertext: &FullCipherText,
+    encoding: BlobEncoding,
+) -> EnftResult<()> {
+    writer.write_all(&encoding.encode(&ciphertext.to_bytes()))?;
+    Ok(())
+}
+
+/// Read a ciphertext, as written by [write_ciphertext], from the given reader.
+/// Unversioned (v0) ciphertexts are read as well.
+pub fn read_ciphertext<R: Read>(
+    reader: &mut R,
+    encoding: BlobEncoding,
+) -> EnftResult<FullCipherText> {
+    let mut contents = vec![];
+    reader.read_to_end(&mut contents)?;
+    FullCipherText::from_bytes(&encoding.decode(&contents)?)
+}
+
+/// Read the width and height prefixed to the image bytes by [load_image], and
+/// check that the remaining bytes hold exactly that many RGBA pixels.
+pub(crate) fn image_dimensions(image_bytes: &[u8]) -> EnftResult<(u32, u32)> {
+    if image_bytes.len() < 8 {
+        return Err(EnftError::ImageMismatch);
+    }
+    let width = u32::from_be_bytes(image_bytes[0..4].try_into().unwrap());
+    let height = u32::from_be_bytes(image_bytes[4..8].try_into().unwrap());
+    // The dimensions are untrusted, so the size may not fit in a usize (e.g. on wasm32).
+    let size = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4));
+    if size != Some(image_bytes.len() - 8) {
+        return Err(EnftError::ImageMismatch);
+    }
+    Ok((width, height))
 }
 
 /// Take the obfuscated image, ciphertext and master key, recover the original
@@ -103,36 +209,220 @@ pub fn recover_image(
     obfuscated_image: &[u8],
     ciphertext: FullCipherText,
     master_key: G1Element,
-) -> Vec<u8> {
-    let cipher = msk_to_cipher(&master_key);
-    let plaintext = cipher.decrypt(&ciphertext.iv, &ciphertext.data).unwrap();
+) -> EnftResult<Vec<u8>> {
+    let plaintext = ciphertext.decrypt(obfuscated_image, &master_key)?;
 
-    let width = u32::from_be_bytes(obfuscated_image[0..4].try_into().unwrap()) as usize;
+    let (width, height) = image_dimensions(obfuscated_image)?;
+    let pixel_indices = ciphertext
+        .region()
+        .pixel_indices(width as usize, height as usize)?;
+    if plaintext.len() != 4 * pixel_indices.len() {
+        return Err(EnftError::ImageMismatch);
+    }
 
+    // this iterates through the selected region and sets the decrypted
+    // values and outputs the recovered image bytes.
     let mut recovered = obfuscated_image.to_vec();
-    let mut pt_idx = 0;
+    for (pixel_idx, values) in pixel_indices.iter().zip(plaintext.chunks_exact(4)) {
+        recovered[4 * pixel_idx + 8..4 * pixel_idx + 12].copy_from_slice(values);
+    }
+    Ok(recovered)
+}
 
-    // this code can be customized!
-    // this iterates through the selected coordinates and sets the decrypted
-    // values and outputs the recovered image bytes.
-    for (i, j) in ciphertext.pixels.iter() {
-        for ii in i * CHUNK_SIZE..(i + 1) * CHUNK_SIZE {
-            for jj in j * CHUNK_SIZE..(j + 1) * CHUNK_SIZE {
-                let pixel_idx = ii * width + jj;
-                for k in 4 * pixel_idx + 8..4 * pixel_idx + 12 {
-                    recovered[k] = plaintext[pt_idx];
-                    pt_idx += 1;
-                }
-            }
-        }
+/// Recover the master key from its encryption under the pubkey of `enc_sk`, and
+/// check that it is the key of the given ciphertext. This lets a reseller who only
+/// holds their encryption key and the listing recover the master key.
+pub fn recover_master_key(
+    enc_msk: &ElGamalEncryption,
+    enc_sk: &Scalar,
+    ciphertext: &FullCipherText,
+    obfuscated_image: &[u8],
+) -> EnftResult<G1Element> {
+    let msk = elgamal::decrypt(enc_msk, enc_sk);
+    match ciphertext.decrypt(obfuscated_image, &msk) {
+        Ok(_) => Ok(msk),
+        Err(EnftError::AuthenticationFailed) => Err(EnftError::WrongKey),
+        Err(e) => Err(e),
     }
-    recovered
+}
+
+/// The HKDF label of the AES key derived from a master key by [derive_aes_key].
+pub const AES_KEY_LABEL: &str = "enft/v1/aes-key";
+
+/// Derive the AES key of a master key with HKDF-SHA256, using the compressed master
+/// key as input keying material, no salt and the label as info. This is what the
+/// TypeScript backend implements as `deriveAesKey`.
+pub fn derive_aes_key(msk: &G1Element, label: &str) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, &msk.to_byte_array())
+        .expand(label.as_bytes(), &mut key)
+        .expect("valid output length");
+    key
+}
+
+/// The cipher with the AES key derived from the master key by [derive_aes_key].
+pub fn msk_to_hkdf_cipher(msk: &G1Element, label: &str) -> Aes256Gcm<U12> {
+    Aes256Gcm::<U12>::new(AesKey::from_bytes(&derive_aes_key(msk, label)).unwrap())
 }
 
 /// Convert the master key G1 element to a cipher (where the AES key is derived
 /// from the master key). The cipher can be used for encryption and decryption.
+/// This legacy key schedule depends on the algorithm of `StdRng`, and is only
+/// kept to decrypt ciphertexts created before [AES_KEY_LABEL] was introduced.
 pub fn msk_to_cipher(msk: &G1Element) -> Aes256Gcm<U12> {
     let mut rng = StdRng::from_seed(Blake2b256::digest(msk.to_byte_array()).digest);
     let key: GenericByteArray<U32> = AesKey::generate(&mut rng);
     Aes256Gcm::<U12>::new(key)
 }
+
+/// Decode a hex string.
+pub fn parse_hex(s: &str) -> EnftResult<Vec<u8>> {
+    Hex::decode(s).map_err(|_| EnftError::InvalidHex)
+}
+
+fn to_byte_array<const N: usize>(bytes: Vec<u8>) -> EnftResult<[u8; N]> {
+    let actual = bytes.len();
+    bytes.try_into().map_err(|_| EnftError::InvalidLength {
+        expected: N,
+        actual,
+    })
+}
+
+/// Decode a G1 element from its 48 byte compressed form.
+pub fn g1_element_from_bytes(bytes: Vec<u8>) -> EnftResult<G1Element> {
+    G1Element::from_byte_array(&to_byte_array(bytes)?).map_err(|_| EnftError::InvalidGroupElement)
+}
+
+/// Decode a scalar from its 32 byte big-endian form.
+pub fn scalar_from_bytes(bytes: Vec<u8>) -> EnftResult<Scalar> {
+    Scalar::from_byte_array(&to_byte_array(bytes)?).map_err(|_| EnftError::InvalidScalar)
+}
+
+/// Decode a hex encoded G1 element, e.g. a master key or an encryption pubkey.
+pub fn parse_g1_element(s: &str) -> EnftResult<G1Element> {
+    g1_element_from_bytes(parse_hex(s)?)
+}
+
+/// Decode a hex encoded scalar, e.g. an encryption private key.
+pub fn parse_scalar(s: &str) -> EnftResult<Scalar> {
+    scalar_from_bytes(parse_hex(s)?)
+}
+
+/// Decode a hex encoded BCS value, e.g. an ElGamalEncryption or an EqualityProof.
+pub fn parse_bcs<T: DeserializeOwned>(s: &str) -> EnftResult<T> {
+    Ok(bcs::from_bytes(&parse_hex(s)?)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::region::{Rectangle, RegionSpec};
+    use fastcrypto::groups::{GroupElement, Scalar as _};
+
+    #[test]
+    fn test_recover_styled_image() {
+        let (width, height) = (30u32, 20u32);
+        let pixels: Vec<u8> = (0..4 * width * height).map(|i| (i % 251) as u8).collect();
+        let image = LoadedImage {
+            data: [
+                width.to_be_bytes().to_vec(),
+                height.to_be_bytes().to_vec(),
+                pixels,
+            ]
+            .concat(),
+            width,
+            height,
+        };
+        let region = Region::Shapes(RegionSpec {
+            rectangles: vec![Rectangle {
+                x: 5,
+                y: 5,
+                width: 12,
+                height: 9,
+            }],
+            polygons: vec![],
+        });
+        let master_key = G1Element::generator() * Scalar::rand(&mut rand::thread_rng());
+
+        for style in [
+            "transparent",
+            "solid:ff8000",
+            "pixelate:4",
+            "blur:2",
+            "noise",
+            "mean",
+        ] {
+            let style: ObfuscationStyle = style.parse().unwrap();
+            let preprocessed = obfuscate_image(&image, region.clone(), &style).unwrap();
+            assert_ne!(preprocessed.obfuscated_image, image.data);
+
+            let ciphertext =
+                FullCipherText::encrypt(&preprocessed, &master_key, &mut rand::thread_rng())
+                    .unwrap();
+            let recovered =
+                recover_image(&preprocessed.obfuscated_image, ciphertext, master_key).unwrap();
+            assert_eq!(recovered, image.data);
+        }
+    }
+
+    #[test]
+    fn test_image_dimensions() {
+        let image = LoadedImage::from_rgba(2, 3, &[0; 24]).unwrap();
+        assert_eq!(image_dimensions(&image.data).unwrap(), (2, 3));
+        assert!(LoadedImage::from_rgba(2, 3, &[0; 23]).is_err());
+        // 4 * 2^31 * 2^31 overflows to 0, which must not match an empty buffer.
+        assert!(LoadedImage::from_rgba(1 << 31, 1 << 31, &[]).is_err());
+        assert!(LoadedImage::from_rgba(u32::MAX, u32::MAX, &[]).is_err());
+        assert!(image_dimensions(&[0; 7]).is_err());
+    }
+
+    #[test]
+    fn test_aes_key_vector() {
+        // Shared with the TypeScript backend, see `deriveAesKey` in backend/images/obfuscate.ts.
+        assert_eq!(
+            Hex::encode(derive_aes_key(&G1Element::generator(), AES_KEY_LABEL)),
+            "6b1bf91413e04c69c5010f23c85ef05ccda990dd099af0432f04d52eba625f4b"
+        );
+    }
+
+    #[test]
+    fn test_recover_master_key() {
+        let mut rng = rand::thread_rng();
+        let master_key = G1Element::generator() * Scalar::rand(&mut rng);
+        let seller_sk = Scalar::rand(&mut rng);
+        let (enc_msk, _) =
+            elgamal::encrypt(&master_key, &(G1Element::generator() * seller_sk), &mut rng);
+
+        let image = LoadedImage {
+            data: [
+                2u32.to_be_bytes().to_vec(),
+                2u32.to_be_bytes().to_vec(),
+                vec![9u8; 16],
+            ]
+            .concat(),
+            width: 2,
+            height: 2,
+        };
+        let region = Region::Mask(vec![(1, 2)]);
+        let preprocessed = obfuscate_image(&image, region, &ObfuscationStyle::default()).unwrap();
+        let ciphertext = FullCipherText::encrypt(&preprocessed, &master_key, &mut rng).unwrap();
+
+        let recovered = recover_master_key(
+            &enc_msk,
+            &seller_sk,
+            &ciphertext,
+            &preprocessed.obfuscated_image,
+        )
+        .unwrap();
+        assert_eq!(recovered, master_key);
+        assert!(matches!(
+            recover_master_key(
+                &enc_msk,
+                &Scalar::rand(&mut rng),
+                &ciphertext,
+                &preprocessed.obfuscated_image
+            ),
+            Err(EnftError::WrongKey)
+        ));
+    }
+}
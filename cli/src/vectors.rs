@@ -0,0 +1,488 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Deterministic test vectors for the equality proof, so that `equality_verify` and
+//! `fiat_shamir_challenge` in the Move package can be checked against the prover here.
+//! The same vectors are written as JSON and as Move `#[test]` functions.
+
+use crate::elgamal::{
+    fiat_shamir_challenge, transfer, verify_equality, ElGamalEncryption, EqualityProof,
+    InvalidProof,
+};
+use crate::error::{EnftError, EnftResult};
+use crate::utils::{parse_g1_element, parse_scalar};
+use fastcrypto::encoding::{Encoding, Hex};
+use fastcrypto::groups::bls12381::{G1Element, Scalar};
+use fastcrypto::groups::{GroupElement, Scalar as _};
+use fastcrypto::serde_helpers::ToFromByteArray;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fmt::Write;
+use std::path::Path;
+
+/// The test vectors generated from one seed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestVectors {
+    pub seed: u64,
+    pub cases: Vec<TestVector>,
+}
+
+/// The arguments of one `equality_verify` call, the challenge it computes and whether
+/// it accepts the proof.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "TestVectorJson", into = "TestVectorJson")]
+pub struct TestVector {
+    pub name: String,
+    pub sender_pk: G1Element,
+    pub receiver_pk: G1Element,
+    pub prev_enc: ElGamalEncryption,
+    pub new_enc: ElGamalEncryption,
+    pub proof: EqualityProof,
+    pub challenge: Scalar,
+    pub expected: Expected,
+}
+
+/// The expected outcome of verifying a test vector. Move only tells valid and invalid
+/// apart, while here the failing check must match as well.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expected {
+    Valid,
+    InvalidS1,
+    InvalidS2,
+    InvalidV,
+}
+
+impl Expected {
+    fn result(&self) -> Result<(), InvalidProof> {
+        match self {
+            Expected::Valid => Ok(()),
+            Expected::InvalidS1 => Err(InvalidProof::S1),
+            Expected::InvalidS2 => Err(InvalidProof::S2),
+            Expected::InvalidV => Err(InvalidProof::V),
+        }
+    }
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.result() {
+            Ok(()) => write!(f, "valid"),
+            Err(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Generate the test vectors for a seed. The same seed always gives the same vectors.
+/// Besides honest transfers they include proofs for another master key and tampered
+/// proofs. Changing a commitment or a statement changes the challenge, so those are
+/// rejected by the first check.
+pub fn generate(seed: u64) -> TestVectors {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let gen = G1Element::generator();
+    let msk = gen * Scalar::rand(&mut rng);
+    let seller_sk = Scalar::rand(&mut rng);
+    let buyer_sk = Scalar::rand(&mut rng);
+    let reseller_buyer_sk = Scalar::rand(&mut rng);
+    let seller_pk = gen * seller_sk;
+    let buyer_pk = gen * buyer_sk;
+    let reseller_buyer_pk = gen * reseller_buyer_sk;
+
+    let (prev_enc, _) = crate::elgamal::encrypt(&msk, &seller_pk, &mut rng);
+    let (new_enc, proof) = transfer(&msk, &prev_enc, &seller_sk, &buyer_pk, &mut rng);
+    let (resale_enc, resale_proof) =
+        transfer(&msk, &new_enc, &buyer_sk, &reseller_buyer_pk, &mut rng);
+    let other_msk = gen * Scalar::rand(&mut rng);
+    let (other_enc, other_proof) = transfer(&other_msk, &prev_enc, &seller_sk, &buyer_pk, &mut rng);
+
+    let tampered = |name: &str, proof: EqualityProof, expected: Expected| {
+        TestVector::new(
+            name,
+            seller_pk,
+            buyer_pk,
+            prev_enc.clone(),
+            new_enc.clone(),
+            proof,
+            expected,
+        )
+    };
+    let one = Scalar::generator();
+
+    let cases = vec![
+        tampered("valid", proof.clone(), Expected::Valid),
+        TestVector::new(
+            "resale",
+            buyer_pk,
+            reseller_buyer_pk,
+            new_enc.clone(),
+            resale_enc,
+            resale_proof,
+            Expected::Valid,
+        ),
+        TestVector::new(
+            "other_master_key",
+            seller_pk,
+            buyer_pk,
+            prev_enc.clone(),
+            other_enc,
+            other_proof,
+            Expected::InvalidV,
+        ),
+        tampered(
+            "tampered_s1",
+            EqualityProof {
+                s1: proof.s1 + one,
+                ..proof.clone()
+            },
+            Expected::InvalidS1,
+        ),
+        tampered(
+            "tampered_s2",
+            EqualityProof {
+                s2: proof.s2 + one,
+                ..proof.clone()
+            },
+            Expected::InvalidS2,
+        ),
+        tampered(
+            "tampered_u1",
+            EqualityProof {
+                u1: proof.u1 + gen,
+                ..proof.clone()
+            },
+            Expected::InvalidS1,
+        ),
+        tampered(
+            "tampered_v",
+            EqualityProof {
+                v: proof.v + gen,
+                ..proof.clone()
+            },
+            Expected::InvalidS1,
+        ),
+        TestVector::new(
+            "wrong_receiver",
+            seller_pk,
+            reseller_buyer_pk,
+            prev_enc.clone(),
+            new_enc.clone(),
+            proof.clone(),
+            Expected::InvalidS1,
+        ),
+        TestVector::new(
+            "swapped_encryptions",
+            seller_pk,
+            buyer_pk,
+            new_enc,
+            prev_enc,
+            proof,
+            Expected::InvalidS1,
+        ),
+    ];
+    TestVectors { seed, cases }
+}
+
+impl TestVectors {
+    /// Check every vector against the prover, see [TestVector::replay].
+    pub fn replay(&self) -> EnftResult<()> {
+        self.cases.iter().try_for_each(TestVector::replay)
+    }
+
+    /// Render the vectors as Move `#[test]` functions to paste into the
+    /// `package::private_nft` module, which has access to its private functions.
+    pub fn to_move_tests(&self) -> String {
+        let mut tests = format!(
+            "    // Equality proof test vectors of seed {}, also written to vectors.json.\n",
+            self.seed
+        );
+        for case in &self.cases {
+            tests.push('\n');
+            case.write_move_test(&mut tests)
+                .expect("Writing to a String cannot fail");
+        }
+        tests
+    }
+
+    pub fn from_json_file(path: impl AsRef<Path>) -> EnftResult<Self> {
+        serde_json::from_slice(&std::fs::read(path)?)
+            .map_err(|e| EnftError::TestVector(e.to_string()))
+    }
+}
+
+impl TestVector {
+    fn new(
+        name: &str,
+        sender_pk: G1Element,
+        receiver_pk: G1Element,
+        prev_enc: ElGamalEncryption,
+        new_enc: ElGamalEncryption,
+        proof: EqualityProof,
+        expected: Expected,
+    ) -> Self {
+        let challenge = fiat_shamir_challenge(
+            &sender_pk,
+            &receiver_pk,
+            &prev_enc,
+            &new_enc,
+            proof.u1,
+            proof.u2,
+            proof.v,
+        );
+        TestVector {
+            name: name.to_string(),
+            sender_pk,
+            receiver_pk,
+            prev_enc,
+            new_enc,
+            proof,
+            challenge,
+            expected,
+        }
+    }
+
+    /// Check that the challenge and the verification outcome are the recorded ones.
+    pub fn replay(&self) -> EnftResult<()> {
+        let challenge = fiat_shamir_challenge(
+            &self.sender_pk,
+            &self.receiver_pk,
+            &self.prev_enc,
+            &self.new_enc,
+            self.proof.u1,
+            self.proof.u2,
+            self.proof.v,
+        );
+        if challenge != self.challenge {
+            return Err(EnftError::TestVector(format!(
+                "Challenge of '{}' does not match",
+                self.name
+            )));
+        }
+
+        let result = verify_equality(
+            &self.sender_pk,
+            &self.receiver_pk,
+            &self.prev_enc,
+            &self.new_enc,
+            &self.proof,
+        );
+        if result != self.expected.result() {
+            let actual = match result {
+                Ok(()) => "valid".to_string(),
+                Err(e) => e.to_string(),
+            };
+            return Err(EnftError::TestVector(format!(
+                "Expected '{}' to be {}, got {}",
+                self.name, self.expected, actual
+            )));
+        }
+        Ok(())
+    }
+
+    fn write_move_test(&self, out: &mut String) -> fmt::Result {
+        let hex = |bytes: &[u8]| Hex::encode(bytes);
+        writeln!(out, "    #[test]")?;
+        writeln!(out, "    fun test_vector_{}() {{", self.name)?;
+        writeln!(
+            out,
+            "        let pk1 = bls12381::g1_from_bytes(&x\"{}\");",
+            hex(&self.sender_pk.to_byte_array())
+        )?;
+        writeln!(
+            out,
+            "        let pk2 = bls12381::g1_from_bytes(&x\"{}\");",
+            hex(&self.receiver_pk.to_byte_array())
+        )?;
+        for (name, enc) in [("enc1", &self.prev_enc), ("enc2", &self.new_enc)] {
+            writeln!(out, "        let {} = ElGamalEncryption {{", name)?;
+            writeln!(
+                out,
+                "            ephemeral: bls12381::g1_from_bytes(&x\"{}\"),",
+                hex(&enc.ephemeral.to_byte_array())
+            )?;
+            writeln!(
+                out,
+                "            ciphertext: bls12381::g1_from_bytes(&x\"{}\"),",
+                hex(&enc.ciphertext.to_byte_array())
+            )?;
+            writeln!(out, "        }};")?;
+        }
+        writeln!(out, "        let proof = EqualityProof {{")?;
+        for (name, scalar) in [("s1", &self.proof.s1), ("s2", &self.proof.s2)] {
+            writeln!(
+                out,
+                "            {}: bls12381::scalar_from_bytes(&x\"{}\"),",
+                name,
+                hex(&scalar.to_byte_array())
+            )?;
+        }
+        for (name, point) in [
+            ("u1", &self.proof.u1),
+            ("u2", &self.proof.u2),
+            ("v", &self.proof.v),
+        ] {
+            writeln!(
+                out,
+                "            {}: bls12381::g1_from_bytes(&x\"{}\"),",
+                name,
+                hex(&point.to_byte_array())
+            )?;
+        }
+        writeln!(out, "        }};")?;
+        writeln!(
+            out,
+            "        let c = fiat_shamir_challenge(&pk1, &pk2, &enc1, &enc2, &proof.u1, &proof.u2, &proof.v);"
+        )?;
+        writeln!(
+            out,
+            "        assert!(*group_ops::bytes(&c) == x\"{}\", 0);",
+            hex(&self.challenge.to_byte_array())
+        )?;
+        let negation = if self.expected == Expected::Valid {
+            ""
+        } else {
+            "!"
+        };
+        writeln!(
+            out,
+            "        assert!({}equality_verify(&pk1, &pk2, &enc1, &enc2, &proof), 1);",
+            negation
+        )?;
+        writeln!(out, "    }}")
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct TestVectorJson {
+    name: String,
+    sender_pk: String,
+    receiver_pk: String,
+    prev_ephemeral: String,
+    prev_ciphertext: String,
+    new_ephemeral: String,
+    new_ciphertext: String,
+    s1: String,
+    s2: String,
+    u1: String,
+    u2: String,
+    v: String,
+    challenge: String,
+    expected: Expected,
+}
+
+impl TryFrom<TestVectorJson> for TestVector {
+    type Error = EnftError;
+
+    fn try_from(json: TestVectorJson) -> Result<Self, Self::Error> {
+        Ok(TestVector {
+            name: json.name,
+            sender_pk: parse_g1_element(&json.sender_pk)?,
+            receiver_pk: parse_g1_element(&json.receiver_pk)?,
+            prev_enc: ElGamalEncryption {
+                ephemeral: parse_g1_element(&json.prev_ephemeral)?,
+                ciphertext: parse_g1_element(&json.prev_ciphertext)?,
+            },
+            new_enc: ElGamalEncryption {
+                ephemeral: parse_g1_element(&json.new_ephemeral)?,
+                ciphertext: parse_g1_element(&json.new_ciphertext)?,
+            },
+            proof: EqualityProof {
+                s1: parse_scalar(&json.s1)?,
+                s2: parse_scalar(&json.s2)?,
+                u1: parse_g1_element(&json.u1)?,
+                u2: parse_g1_element(&json.u2)?,
+                v: parse_g1_element(&json.v)?,
+            },
+            challenge: parse_scalar(&json.challenge)?,
+            expected: json.expected,
+        })
+    }
+}
+
+impl From<TestVector> for TestVectorJson {
+    fn from(case: TestVector) -> Self {
+        TestVectorJson {
+            name: case.name,
+            sender_pk: Hex::encode(case.sender_pk.to_byte_array()),
+            receiver_pk: Hex::encode(case.receiver_pk.to_byte_array()),
+            prev_ephemeral: Hex::encode(case.prev_enc.ephemeral.to_byte_array()),
+            prev_ciphertext: Hex::encode(case.prev_enc.ciphertext.to_byte_array()),
+            new_ephemeral: Hex::encode(case.new_enc.ephemeral.to_byte_array()),
+            new_ciphertext: Hex::encode(case.new_enc.ciphertext.to_byte_array()),
+            s1: Hex::encode(case.proof.s1.to_byte_array()),
+            s2: Hex::encode(case.proof.s2.to_byte_array()),
+            u1: Hex::encode(case.proof.u1.to_byte_array()),
+            u2: Hex::encode(case.proof.u2.to_byte_array()),
+            v: Hex::encode(case.proof.v.to_byte_array()),
+            challenge: Hex::encode(case.challenge.to_byte_array()),
+            expected: case.expected,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The vectors committed with the Move package tests, which must not change.
+    const VECTORS_JSON: &str = include_str!("../testdata/vectors.json");
+    const PRIVATE_NFT_MOVE: &str = include_str!("../../package/sources/private_nft.move");
+
+    #[test]
+    fn test_replay_vector_file() {
+        let vectors: TestVectors = serde_json::from_str(VECTORS_JSON).unwrap();
+        vectors.replay().unwrap();
+
+        // The Move package runs the same vectors.
+        assert!(PRIVATE_NFT_MOVE.contains(&vectors.to_move_tests()));
+
+        // Generated vectors are deterministic and pass the replay.
+        let generated = generate(1);
+        assert_eq!(generate(1), generated);
+        assert_ne!(generate(2), generated);
+        generated.replay().unwrap();
+
+        // A vector with the wrong outcome or challenge is reported.
+        let mut wrong = vectors.clone();
+        wrong.cases[0].expected = Expected::InvalidV;
+        assert!(matches!(wrong.replay(), Err(EnftError::TestVector(_))));
+        let mut wrong = vectors;
+        wrong.cases[2].challenge = wrong.cases[2].challenge + Scalar::generator();
+        assert!(matches!(wrong.replay(), Err(EnftError::TestVector(_))));
+    }
+
+    #[test]
+    fn test_move_test_new_vector() {
+        // The transfer in test_new of the Move package, which equality_verify accepts.
+        let json = r#"{
+            "name": "move_test_new",
+            "sender_pk": "8f6823e107967915032bb701bfbf2c6f1f5fe4d584d9be1db7a0010f2b3510eac3c7176630a25b77d9e0a350a2f2a728",
+            "receiver_pk": "8463affac6a1eb9b22caabfd5d2cefeacdf8a84175a588d3ee19f9ad4f53eeed7f5cfe464505792db811ea68cebaf136",
+            "prev_ephemeral": "8cfca733533a0a3421cd93579a57f6ab68ead466105d9a25124bf8a5998124ebf1e94e8694e2c9bbb01b1b29fe7c2088",
+            "prev_ciphertext": "b6495bead5ac0759fd77088e7665c0ca1dc02ce866f6971bbe236e95a6533b46a2896bb282f70e5a29234ed0e70154ac",
+            "new_ephemeral": "98dc2c7b9ea00fce02bd0abcc83523ce5aab00b0fe671bdab392649fddac7a55212181be3bf9c71d35a7745af509c8c5",
+            "new_ciphertext": "8990946a8bf81675185640e699d49d70db2fbcd094f7ab89d8c89d67d897937acb8bf759f7c58960a83eb8a60efc4984",
+            "s1": "2fb95792192b8357e4304233474c516798526fd0d187b36c90af83e87ef6056f",
+            "s2": "402dd9ec755e8e8d4f4ba57d5419f5c6b5791b359ea9e96fb5dbf653a58e5f52",
+            "u1": "a9b484d6927e3a758d37464d332b0807d97029d1e158e9c9cc4a8bf28228183c8d183efc9d686f5c96535e4c39e401d6",
+            "u2": "a1d98ef7dc3ab15ef1a344c06b2840d8e554fdef7364f80ad462b783e060ebe1f92d99f317e8a0cac838f00b1ffd40f1",
+            "v": "8975a7437dda0bf41a095090884954b0f9f93cf304f2d33a20b90d8122e294f4596b7f14428df0d4a82b27f4d63bbb5f",
+            "challenge": "0000000000000000000000000000000000000000000000000000000000000000",
+            "expected": "valid"
+        }"#;
+        // The Move test does not record the challenge, so it is computed here.
+        let mut case: TestVector = serde_json::from_str(json).unwrap();
+        case.challenge = fiat_shamir_challenge(
+            &case.sender_pk,
+            &case.receiver_pk,
+            &case.prev_enc,
+            &case.new_enc,
+            case.proof.u1,
+            case.proof.u2,
+            case.proof.v,
+        );
+        assert_eq!(case.challenge.to_byte_array()[0], 0);
+        case.replay().unwrap();
+    }
+}
@@ -0,0 +1,288 @@
+#ifndef ENFT_H
+#define ENFT_H
+
+/* Generated by cbindgen from src/lib.rs. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The size of a private key.
+ */
+#define ENFT_SCALAR_LENGTH 32
+
+/**
+ * The size of a master key or a pubkey.
+ */
+#define ENFT_G1_LENGTH 48
+
+/**
+ * The size of an encrypted master key.
+ */
+#define ENFT_ENCRYPTION_LENGTH 96
+
+/**
+ * The size of an equality proof.
+ */
+#define ENFT_PROOF_LENGTH 208
+
+/**
+ * Success.
+ */
+#define ENFT_OK 0
+
+/**
+ * The equality proof was rejected.
+ */
+#define ENFT_PROOF_REJECTED 1
+
+/**
+ * The hex encoding is invalid.
+ */
+#define ENFT_ERROR_INVALID_HEX 10
+
+/**
+ * An input has the wrong length.
+ */
+#define ENFT_ERROR_INVALID_LENGTH 11
+
+/**
+ * A key or encryption is not a valid G1 element.
+ */
+#define ENFT_ERROR_INVALID_GROUP_ELEMENT 12
+
+/**
+ * A private key or proof scalar is invalid.
+ */
+#define ENFT_ERROR_INVALID_SCALAR 13
+
+/**
+ * BCS deserialization failed.
+ */
+#define ENFT_ERROR_BCS 14
+
+/**
+ * AES-GCM authentication failed, e.g. for another master key.
+ */
+#define ENFT_ERROR_AUTHENTICATION_FAILED 15
+
+/**
+ * The ciphertext does not match the image, or the image has the wrong size.
+ */
+#define ENFT_ERROR_IMAGE_MISMATCH 16
+
+/**
+ * An image could not be decoded.
+ */
+#define ENFT_ERROR_IMAGE 17
+
+/**
+ * An I/O error.
+ */
+#define ENFT_ERROR_IO 18
+
+/**
+ * The mnemonic is invalid.
+ */
+#define ENFT_ERROR_INVALID_MNEMONIC 19
+
+/**
+ * The region or sampling strategy is invalid.
+ */
+#define ENFT_ERROR_INVALID_REGION 20
+
+/**
+ * The ciphertext format version is not supported.
+ */
+#define ENFT_ERROR_UNSUPPORTED_VERSION 21
+
+/**
+ * The private key does not decrypt the encrypted master key.
+ */
+#define ENFT_ERROR_WRONG_KEY 22
+
+/**
+ * A keystore error.
+ */
+#define ENFT_ERROR_KEYSTORE 23
+
+/**
+ * The key server rejected the token.
+ */
+#define ENFT_ERROR_UNAUTHORIZED 24
+
+/**
+ * A key server request failed.
+ */
+#define ENFT_ERROR_KEY_SERVER 25
+
+/**
+ * Threshold decryption failed.
+ */
+#define ENFT_ERROR_THRESHOLD 26
+
+/**
+ * The base64 encoding is invalid.
+ */
+#define ENFT_ERROR_INVALID_BASE64 27
+
+/**
+ * A test vector does not replay.
+ */
+#define ENFT_ERROR_TEST_VECTOR 28
+
+/**
+ * A required pointer argument is null.
+ */
+#define ENFT_ERROR_NULL_POINTER -1
+
+/**
+ * The library panicked. This is a bug.
+ */
+#define ENFT_ERROR_PANIC -2
+
+/**
+ * A buffer allocated by the library. Free it with [enft_buffer_free].
+ */
+typedef struct EnftBuffer {
+  uint8_t *data;
+  size_t len;
+} EnftBuffer;
+
+/**
+ * An image given by the caller as `width * height` RGBA pixels.
+ */
+typedef struct EnftImage {
+  uint32_t width;
+  uint32_t height;
+  const uint8_t *rgba;
+  size_t rgba_len;
+} EnftImage;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Generate a master key.
+ *
+ * # Safety
+ * `out_msk` must point to [ENFT_G1_LENGTH] writable bytes.
+ */
+int32_t enft_generate_master_key(uint8_t *out_msk);
+
+/**
+ * Generate an encryption key pair.
+ *
+ * # Safety
+ * `out_sk` must point to [ENFT_SCALAR_LENGTH] and `out_pk` to [ENFT_G1_LENGTH]
+ * writable bytes.
+ */
+int32_t enft_generate_encryption_key(uint8_t *out_sk, uint8_t *out_pk);
+
+/**
+ * Compute the encryption pubkey of a private key.
+ *
+ * # Safety
+ * `sk` must point to [ENFT_SCALAR_LENGTH] bytes and `out_pk` to [ENFT_G1_LENGTH]
+ * writable bytes.
+ */
+int32_t enft_encryption_public_key(const uint8_t *sk, uint8_t *out_pk);
+
+/**
+ * Encrypt the master key under a pubkey.
+ *
+ * # Safety
+ * `msk` and `pk` must point to [ENFT_G1_LENGTH] bytes and `out_enc` to
+ * [ENFT_ENCRYPTION_LENGTH] writable bytes.
+ */
+int32_t enft_encrypt_master_key(const uint8_t *msk, const uint8_t *pk, uint8_t *out_enc);
+
+/**
+ * Decrypt the master key with the private key of the pubkey it was encrypted to.
+ *
+ * # Safety
+ * `enc` must point to [ENFT_ENCRYPTION_LENGTH] bytes, `sk` to [ENFT_SCALAR_LENGTH]
+ * bytes and `out_msk` to [ENFT_G1_LENGTH] writable bytes.
+ */
+int32_t enft_decrypt_master_key(const uint8_t *enc, const uint8_t *sk, uint8_t *out_msk);
+
+/**
+ * Re-encrypt the master key under the receiver's pubkey and prove that it is the key
+ * of the previous encryption under the pubkey of `sender_sk`.
+ *
+ * # Safety
+ * `msk` and `receiver_pk` must point to [ENFT_G1_LENGTH] bytes, `prev_enc` to
+ * [ENFT_ENCRYPTION_LENGTH] bytes, `sender_sk` to [ENFT_SCALAR_LENGTH] bytes, `out_enc`
+ * to [ENFT_ENCRYPTION_LENGTH] and `out_proof` to [ENFT_PROOF_LENGTH] writable bytes.
+ */
+int32_t enft_transfer(const uint8_t *msk,
+                      const uint8_t *prev_enc,
+                      const uint8_t *sender_sk,
+                      const uint8_t *receiver_pk,
+                      uint8_t *out_enc,
+                      uint8_t *out_proof);
+
+/**
+ * Verify the equality proof of a transfer. Returns [ENFT_PROOF_REJECTED] if the proof
+ * is rejected.
+ *
+ * # Safety
+ * `sender_pk` and `receiver_pk` must point to [ENFT_G1_LENGTH] bytes, `prev_enc` and
+ * `new_enc` to [ENFT_ENCRYPTION_LENGTH] bytes and `proof` to [ENFT_PROOF_LENGTH] bytes.
+ */
+int32_t enft_verify_equality(const uint8_t *sender_pk,
+                             const uint8_t *receiver_pk,
+                             const uint8_t *prev_enc,
+                             const uint8_t *new_enc,
+                             const uint8_t *proof);
+
+/**
+ * Obfuscate chunks of an image chosen by the sampling strategy, e.g. `percent:20`
+ * (the default if `strategy` is null), and encrypt them under the master key. Gives
+ * the RGBA pixels of the obfuscated image and the ciphertext, as written by
+ * `enft-cli --encoding raw encrypt`.
+ *
+ * # Safety
+ * `image` must point to an image whose `rgba` points to `rgba_len` bytes, `msk` to
+ * [ENFT_G1_LENGTH] bytes, `strategy` must be null or a NUL terminated string, and
+ * `out_rgba` and `out_ciphertext` must be writable. The buffers written to them must
+ * be freed with [enft_buffer_free].
+ */
+int32_t enft_encrypt_image(const EnftImage *image,
+                           const uint8_t *msk,
+                           const char *strategy,
+                           EnftBuffer *out_rgba,
+                           EnftBuffer *out_ciphertext);
+
+/**
+ * Recover the RGBA pixels of the original image from the obfuscated image, the
+ * ciphertext and the master key.
+ *
+ * # Safety
+ * `image` must point to an image whose `rgba` points to `rgba_len` bytes, `ciphertext`
+ * to `ciphertext_len` bytes, `msk` to [ENFT_G1_LENGTH] bytes, and `out_rgba` must be
+ * writable. The buffer written to it must be freed with [enft_buffer_free].
+ */
+int32_t enft_decrypt_image(const EnftImage *image,
+                           const uint8_t *ciphertext,
+                           size_t ciphertext_len,
+                           const uint8_t *msk,
+                           EnftBuffer *out_rgba);
+
+/**
+ * Free a buffer allocated by the library. Freeing an empty buffer does nothing.
+ *
+ * # Safety
+ * `buffer` must have been written by this library and not freed before.
+ */
+void enft_buffer_free(EnftBuffer buffer);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ENFT_H */
@@ -0,0 +1,620 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A C ABI for the encrypted NFT library, for native wallets. The header
+//! `include/enft.h` is generated from this file with cbindgen.
+//!
+//! Keys, encrypted master keys and proofs have fixed sizes and are passed as pointers
+//! to buffers of that size in the CLI's encoding: compressed G1 elements, big-endian
+//! scalars and BCS. The caller owns all input and output buffers of fixed size.
+//! Images and ciphertexts have variable sizes and are returned as an [EnftBuffer]
+//! allocated by the library, which the caller must free with [enft_buffer_free].
+//!
+//! Every function returns [ENFT_OK] on success, and otherwise a negative code of this
+//! library or the positive exit code of the CLI for the error, e.g. [ENFT_PROOF_REJECTED]
+//! or [ENFT_ERROR_INVALID_GROUP_ELEMENT].
+//! Outputs are only written on success.
+
+use enft_cli::elgamal;
+use enft_cli::elgamal::{ElGamalEncryption, EqualityProof};
+use enft_cli::error::EnftError;
+use enft_cli::region::ChunkGeometry;
+use enft_cli::sampling::BuiltinStrategy;
+use enft_cli::style::ObfuscationStyle;
+use enft_cli::utils;
+use enft_cli::utils::{g1_element_from_bytes, scalar_from_bytes, LoadedImage};
+use enft_cli::FullCipherText;
+use fastcrypto::groups::bls12381::{G1Element, Scalar};
+use fastcrypto::groups::{GroupElement, Scalar as _};
+use fastcrypto::serde_helpers::ToFromByteArray;
+use std::ffi::{c_char, CStr};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// The size of a private key.
+pub const ENFT_SCALAR_LENGTH: usize = 32;
+
+/// The size of a master key or a pubkey.
+pub const ENFT_G1_LENGTH: usize = 48;
+
+/// The size of an encrypted master key.
+pub const ENFT_ENCRYPTION_LENGTH: usize = 96;
+
+/// The size of an equality proof.
+pub const ENFT_PROOF_LENGTH: usize = 208;
+
+/// Success.
+pub const ENFT_OK: i32 = 0;
+
+/// The equality proof was rejected.
+pub const ENFT_PROOF_REJECTED: i32 = 1;
+
+/// The hex encoding is invalid.
+pub const ENFT_ERROR_INVALID_HEX: i32 = 10;
+
+/// An input has the wrong length.
+pub const ENFT_ERROR_INVALID_LENGTH: i32 = 11;
+
+/// A key or encryption is not a valid G1 element.
+pub const ENFT_ERROR_INVALID_GROUP_ELEMENT: i32 = 12;
+
+/// A private key or proof scalar is invalid.
+pub const ENFT_ERROR_INVALID_SCALAR: i32 = 13;
+
+/// BCS deserialization failed.
+pub const ENFT_ERROR_BCS: i32 = 14;
+
+/// AES-GCM authentication failed, e.g. for another master key.
+pub const ENFT_ERROR_AUTHENTICATION_FAILED: i32 = 15;
+
+/// The ciphertext does not match the image, or the image has the wrong size.
+pub const ENFT_ERROR_IMAGE_MISMATCH: i32 = 16;
+
+/// An image could not be decoded.
+pub const ENFT_ERROR_IMAGE: i32 = 17;
+
+/// An I/O error.
+pub const ENFT_ERROR_IO: i32 = 18;
+
+/// The mnemonic is invalid.
+pub const ENFT_ERROR_INVALID_MNEMONIC: i32 = 19;
+
+/// The region or sampling strategy is invalid.
+pub const ENFT_ERROR_INVALID_REGION: i32 = 20;
+
+/// The ciphertext format version is not supported.
+pub const ENFT_ERROR_UNSUPPORTED_VERSION: i32 = 21;
+
+/// The private key does not decrypt the encrypted master key.
+pub const ENFT_ERROR_WRONG_KEY: i32 = 22;
+
+/// A keystore error.
+pub const ENFT_ERROR_KEYSTORE: i32 = 23;
+
+/// The key server rejected the token.
+pub const ENFT_ERROR_UNAUTHORIZED: i32 = 24;
+
+/// A key server request failed.
+pub const ENFT_ERROR_KEY_SERVER: i32 = 25;
+
+/// Threshold decryption failed.
+pub const ENFT_ERROR_THRESHOLD: i32 = 26;
+
+/// The base64 encoding is invalid.
+pub const ENFT_ERROR_INVALID_BASE64: i32 = 27;
+
+/// A test vector does not replay.
+pub const ENFT_ERROR_TEST_VECTOR: i32 = 28;
+
+/// A required pointer argument is null.
+pub const ENFT_ERROR_NULL_POINTER: i32 = -1;
+
+/// The library panicked. This is a bug.
+pub const ENFT_ERROR_PANIC: i32 = -2;
+
+/// A buffer allocated by the library. Free it with [enft_buffer_free].
+#[repr(C)]
+pub struct EnftBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// An image given by the caller as `width * height` RGBA pixels.
+#[repr(C)]
+pub struct EnftImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: *const u8,
+    pub rgba_len: usize,
+}
+
+enum Error {
+    NullPointer,
+    Enft(EnftError),
+}
+
+impl From<EnftError> for Error {
+    fn from(e: EnftError) -> Self {
+        Error::Enft(e)
+    }
+}
+
+impl From<bcs::Error> for Error {
+    fn from(e: bcs::Error) -> Self {
+        Error::Enft(e.into())
+    }
+}
+
+/// Run the body of a function and turn its result into a status code.
+fn status(f: impl FnOnce() -> Result<(), Error>) -> i32 {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => ENFT_OK,
+        Ok(Err(Error::NullPointer)) => ENFT_ERROR_NULL_POINTER,
+        Ok(Err(Error::Enft(e))) => e.exit_code(),
+        Err(_) => ENFT_ERROR_PANIC,
+    }
+}
+
+unsafe fn input<'a>(ptr: *const u8, len: usize) -> Result<&'a [u8], Error> {
+    if ptr.is_null() {
+        return Err(Error::NullPointer);
+    }
+    Ok(std::slice::from_raw_parts(ptr, len))
+}
+
+unsafe fn g1_input(ptr: *const u8) -> Result<G1Element, Error> {
+    Ok(g1_element_from_bytes(input(ptr, ENFT_G1_LENGTH)?.to_vec())?)
+}
+
+unsafe fn scalar_input(ptr: *const u8) -> Result<Scalar, Error> {
+    Ok(scalar_from_bytes(input(ptr, ENFT_SCALAR_LENGTH)?.to_vec())?)
+}
+
+unsafe fn encryption_input(ptr: *const u8) -> Result<ElGamalEncryption, Error> {
+    Ok(bcs::from_bytes(input(ptr, ENFT_ENCRYPTION_LENGTH)?)?)
+}
+
+unsafe fn image_input(image: *const EnftImage) -> Result<LoadedImage, Error> {
+    let image = image.as_ref().ok_or(Error::NullPointer)?;
+    let rgba = input(image.rgba, image.rgba_len)?;
+    Ok(LoadedImage::from_rgba(image.width, image.height, rgba)?)
+}
+
+fn check_outputs<T>(outputs: &[*mut T]) -> Result<(), Error> {
+    if outputs.iter().any(|ptr| ptr.is_null()) {
+        return Err(Error::NullPointer);
+    }
+    Ok(())
+}
+
+/// Copy to an output buffer that was checked with [check_outputs].
+unsafe fn output(ptr: *mut u8, bytes: &[u8]) {
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len());
+}
+
+/// Hand a buffer to the caller through an output that was checked with [check_outputs].
+unsafe fn output_buffer(ptr: *mut EnftBuffer, bytes: Vec<u8>) {
+    let len = bytes.len();
+    let data = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+    ptr.write(EnftBuffer { data, len });
+}
+
+/// Generate a master key.
+///
+/// # Safety
+/// `out_msk` must point to [ENFT_G1_LENGTH] writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn enft_generate_master_key(out_msk: *mut u8) -> i32 {
+    status(|| {
+        check_outputs(&[out_msk])?;
+        let msk = G1Element::generator() * Scalar::rand(&mut rand::thread_rng());
+        output(out_msk, &msk.to_byte_array());
+        Ok(())
+    })
+}
+
+/// Generate an encryption key pair.
+///
+/// # Safety
+/// `out_sk` must point to [ENFT_SCALAR_LENGTH] and `out_pk` to [ENFT_G1_LENGTH]
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn enft_generate_encryption_key(out_sk: *mut u8, out_pk: *mut u8) -> i32 {
+    status(|| {
+        check_outputs(&[out_sk, out_pk])?;
+        let sk = Scalar::rand(&mut rand::thread_rng());
+        output(out_sk, &sk.to_byte_array());
+        output(out_pk, &(G1Element::generator() * sk).to_byte_array());
+        Ok(())
+    })
+}
+
+/// Compute the encryption pubkey of a private key.
+///
+/// # Safety
+/// `sk` must point to [ENFT_SCALAR_LENGTH] bytes and `out_pk` to [ENFT_G1_LENGTH]
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn enft_encryption_public_key(sk: *const u8, out_pk: *mut u8) -> i32 {
+    status(|| {
+        check_outputs(&[out_pk])?;
+        let pk = G1Element::generator() * scalar_input(sk)?;
+        output(out_pk, &pk.to_byte_array());
+        Ok(())
+    })
+}
+
+/// Encrypt the master key under a pubkey.
+///
+/// # Safety
+/// `msk` and `pk` must point to [ENFT_G1_LENGTH] bytes and `out_enc` to
+/// [ENFT_ENCRYPTION_LENGTH] writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn enft_encrypt_master_key(
+    msk: *const u8,
+    pk: *const u8,
+    out_enc: *mut u8,
+) -> i32 {
+    status(|| {
+        check_outputs(&[out_enc])?;
+        let (enc, _) = elgamal::encrypt(&g1_input(msk)?, &g1_input(pk)?, &mut rand::thread_rng());
+        output(out_enc, &bcs::to_bytes(&enc)?);
+        Ok(())
+    })
+}
+
+/// Decrypt the master key with the private key of the pubkey it was encrypted to.
+///
+/// # Safety
+/// `enc` must point to [ENFT_ENCRYPTION_LENGTH] bytes, `sk` to [ENFT_SCALAR_LENGTH]
+/// bytes and `out_msk` to [ENFT_G1_LENGTH] writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn enft_decrypt_master_key(
+    enc: *const u8,
+    sk: *const u8,
+    out_msk: *mut u8,
+) -> i32 {
+    status(|| {
+        check_outputs(&[out_msk])?;
+        let msk = elgamal::decrypt(&encryption_input(enc)?, &scalar_input(sk)?);
+        output(out_msk, &msk.to_byte_array());
+        Ok(())
+    })
+}
+
+/// Re-encrypt the master key under the receiver's pubkey and prove that it is the key
+/// of the previous encryption under the pubkey of `sender_sk`.
+///
+/// # Safety
+/// `msk` and `receiver_pk` must point to [ENFT_G1_LENGTH] bytes, `prev_enc` to
+/// [ENFT_ENCRYPTION_LENGTH] bytes, `sender_sk` to [ENFT_SCALAR_LENGTH] bytes, `out_enc`
+/// to [ENFT_ENCRYPTION_LENGTH] and `out_proof` to [ENFT_PROOF_LENGTH] writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn enft_transfer(
+    msk: *const u8,
+    prev_enc: *const u8,
+    sender_sk: *const u8,
+    receiver_pk: *const u8,
+    out_enc: *mut u8,
+    out_proof: *mut u8,
+) -> i32 {
+    status(|| {
+        check_outputs(&[out_enc, out_proof])?;
+        let (enc, proof) = elgamal::transfer(
+            &g1_input(msk)?,
+            &encryption_input(prev_enc)?,
+            &scalar_input(sender_sk)?,
+            &g1_input(receiver_pk)?,
+            &mut rand::thread_rng(),
+        );
+        output(out_enc, &bcs::to_bytes(&enc)?);
+        output(out_proof, &bcs::to_bytes(&proof)?);
+        Ok(())
+    })
+}
+
+/// Verify the equality proof of a transfer. Returns [ENFT_PROOF_REJECTED] if the proof
+/// is rejected.
+///
+/// # Safety
+/// `sender_pk` and `receiver_pk` must point to [ENFT_G1_LENGTH] bytes, `prev_enc` and
+/// `new_enc` to [ENFT_ENCRYPTION_LENGTH] bytes and `proof` to [ENFT_PROOF_LENGTH] bytes.
+#[no_mangle]
+pub unsafe extern "C" fn enft_verify_equality(
+    sender_pk: *const u8,
+    receiver_pk: *const u8,
+    prev_enc: *const u8,
+    new_enc: *const u8,
+    proof: *const u8,
+) -> i32 {
+    status(|| {
+        let proof: EqualityProof = bcs::from_bytes(input(proof, ENFT_PROOF_LENGTH)?)?;
+        elgamal::verify_equality(
+            &g1_input(sender_pk)?,
+            &g1_input(receiver_pk)?,
+            &encryption_input(prev_enc)?,
+            &encryption_input(new_enc)?,
+            &proof,
+        )
+        .map_err(EnftError::from)?;
+        Ok(())
+    })
+}
+
+/// Obfuscate chunks of an image chosen by the sampling strategy, e.g. `percent:20`
+/// (the default if `strategy` is null), and encrypt them under the master key. Gives
+/// the RGBA pixels of the obfuscated image and the ciphertext, as written by
+/// `enft-cli --encoding raw encrypt`.
+///
+/// # Safety
+/// `image` must point to an image whose `rgba` points to `rgba_len` bytes, `msk` to
+/// [ENFT_G1_LENGTH] bytes, `strategy` must be null or a NUL terminated string, and
+/// `out_rgba` and `out_ciphertext` must be writable. The buffers written to them must
+/// be freed with [enft_buffer_free].
+#[no_mangle]
+pub unsafe extern "C" fn enft_encrypt_image(
+    image: *const EnftImage,
+    msk: *const u8,
+    strategy: *const c_char,
+    out_rgba: *mut EnftBuffer,
+    out_ciphertext: *mut EnftBuffer,
+) -> i32 {
+    status(|| {
+        check_outputs(&[out_rgba, out_ciphertext])?;
+        let image = image_input(image)?;
+        let msk = g1_input(msk)?;
+        let strategy = if strategy.is_null() {
+            BuiltinStrategy::default()
+        } else {
+            CStr::from_ptr(strategy)
+                .to_str()
+                .map_err(|e| e.to_string())
+                .and_then(str::parse)
+                .map_err(EnftError::InvalidRegion)?
+        };
+        let preprocessed = utils::sample_image(
+            &image,
+            &strategy,
+            ChunkGeometry::default(),
+            &ObfuscationStyle::default(),
+        )?;
+        let ciphertext = FullCipherText::encrypt(&preprocessed, &msk, &mut rand::thread_rng())?;
+        output_buffer(out_rgba, preprocessed.obfuscated_image[8..].to_vec());
+        output_buffer(out_ciphertext, ciphertext.to_bytes());
+        Ok(())
+    })
+}
+
+/// Recover the RGBA pixels of the original image from the obfuscated image, the
+/// ciphertext and the master key.
+///
+/// # Safety
+/// `image` must point to an image whose `rgba` points to `rgba_len` bytes, `ciphertext`
+/// to `ciphertext_len` bytes, `msk` to [ENFT_G1_LENGTH] bytes, and `out_rgba` must be
+/// writable. The buffer written to it must be freed with [enft_buffer_free].
+#[no_mangle]
+pub unsafe extern "C" fn enft_decrypt_image(
+    image: *const EnftImage,
+    ciphertext: *const u8,
+    ciphertext_len: usize,
+    msk: *const u8,
+    out_rgba: *mut EnftBuffer,
+) -> i32 {
+    status(|| {
+        check_outputs(&[out_rgba])?;
+        let image = image_input(image)?;
+        let ciphertext = FullCipherText::from_bytes(input(ciphertext, ciphertext_len)?)?;
+        let recovered = utils::recover_image(&image.data, ciphertext, g1_input(msk)?)?;
+        output_buffer(out_rgba, recovered[8..].to_vec());
+        Ok(())
+    })
+}
+
+/// Free a buffer allocated by the library. Freeing an empty buffer does nothing.
+///
+/// # Safety
+/// `buffer` must have been written by this library and not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn enft_buffer_free(buffer: EnftBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use enft_cli::elgamal::InvalidProof;
+
+    #[test]
+    fn test_transfer_and_verify() {
+        let mut msk = [0u8; ENFT_G1_LENGTH];
+        let (mut seller_sk, mut seller_pk) = ([0u8; ENFT_SCALAR_LENGTH], [0u8; ENFT_G1_LENGTH]);
+        let (mut buyer_sk, mut buyer_pk) = ([0u8; ENFT_SCALAR_LENGTH], [0u8; ENFT_G1_LENGTH]);
+        let mut enc = [0u8; ENFT_ENCRYPTION_LENGTH];
+        let mut new_enc = [0u8; ENFT_ENCRYPTION_LENGTH];
+        let mut proof = [0u8; ENFT_PROOF_LENGTH];
+        let mut recovered = [0u8; ENFT_G1_LENGTH];
+        unsafe {
+            assert_eq!(enft_generate_master_key(msk.as_mut_ptr()), ENFT_OK);
+            assert_eq!(
+                enft_generate_encryption_key(seller_sk.as_mut_ptr(), seller_pk.as_mut_ptr()),
+                ENFT_OK
+            );
+            assert_eq!(
+                enft_generate_encryption_key(buyer_sk.as_mut_ptr(), buyer_pk.as_mut_ptr()),
+                ENFT_OK
+            );
+            assert_eq!(
+                enft_encrypt_master_key(msk.as_ptr(), seller_pk.as_ptr(), enc.as_mut_ptr()),
+                ENFT_OK
+            );
+            assert_eq!(
+                enft_transfer(
+                    msk.as_ptr(),
+                    enc.as_ptr(),
+                    seller_sk.as_ptr(),
+                    buyer_pk.as_ptr(),
+                    new_enc.as_mut_ptr(),
+                    proof.as_mut_ptr()
+                ),
+                ENFT_OK
+            );
+            assert_eq!(
+                enft_decrypt_master_key(
+                    new_enc.as_ptr(),
+                    buyer_sk.as_ptr(),
+                    recovered.as_mut_ptr()
+                ),
+                ENFT_OK
+            );
+            assert_eq!(recovered, msk);
+
+            let verify = |receiver_pk: &[u8]| {
+                enft_verify_equality(
+                    seller_pk.as_ptr(),
+                    receiver_pk.as_ptr(),
+                    enc.as_ptr(),
+                    new_enc.as_ptr(),
+                    proof.as_ptr(),
+                )
+            };
+            assert_eq!(verify(&buyer_pk), ENFT_OK);
+            assert_eq!(verify(&seller_pk), ENFT_PROOF_REJECTED);
+            assert_eq!(
+                verify(&[0u8; ENFT_G1_LENGTH]),
+                ENFT_ERROR_INVALID_GROUP_ELEMENT
+            );
+            assert_eq!(
+                enft_generate_master_key(std::ptr::null_mut()),
+                ENFT_ERROR_NULL_POINTER
+            );
+        }
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt_image() {
+        let (width, height) = (250u32, 120u32);
+        let rgba: Vec<u8> = (0..width * height * 4).map(|i| (i % 251) as u8).collect();
+        let image = EnftImage {
+            width,
+            height,
+            rgba: rgba.as_ptr(),
+            rgba_len: rgba.len(),
+        };
+        let mut msk = [0u8; ENFT_G1_LENGTH];
+        let empty = || EnftBuffer {
+            data: std::ptr::null_mut(),
+            len: 0,
+        };
+        let (mut obfuscated, mut ciphertext, mut recovered) = (empty(), empty(), empty());
+        unsafe {
+            assert_eq!(enft_generate_master_key(msk.as_mut_ptr()), ENFT_OK);
+            assert_eq!(
+                enft_encrypt_image(
+                    &image,
+                    msk.as_ptr(),
+                    c"checkerboard".as_ptr(),
+                    &mut obfuscated,
+                    &mut ciphertext
+                ),
+                ENFT_OK
+            );
+            let obfuscated_image = EnftImage {
+                width,
+                height,
+                rgba: obfuscated.data,
+                rgba_len: obfuscated.len,
+            };
+            assert_eq!(
+                enft_decrypt_image(
+                    &obfuscated_image,
+                    ciphertext.data,
+                    ciphertext.len,
+                    msk.as_ptr(),
+                    &mut recovered
+                ),
+                ENFT_OK
+            );
+            assert_eq!(
+                std::slice::from_raw_parts(recovered.data, recovered.len),
+                rgba
+            );
+            assert_eq!(
+                enft_encrypt_image(
+                    &image,
+                    msk.as_ptr(),
+                    c"sometimes".as_ptr(),
+                    &mut empty(),
+                    &mut empty()
+                ),
+                ENFT_ERROR_INVALID_REGION
+            );
+            enft_buffer_free(obfuscated);
+            enft_buffer_free(ciphertext);
+            enft_buffer_free(recovered);
+        }
+    }
+
+    #[test]
+    fn test_error_codes() {
+        // The codes in the header are the exit codes of the CLI.
+        let errors = [
+            (
+                EnftError::ProofRejected(InvalidProof::S1),
+                ENFT_PROOF_REJECTED,
+            ),
+            (EnftError::InvalidHex, ENFT_ERROR_INVALID_HEX),
+            (
+                EnftError::InvalidLength {
+                    expected: 1,
+                    actual: 2,
+                },
+                ENFT_ERROR_INVALID_LENGTH,
+            ),
+            (
+                EnftError::InvalidGroupElement,
+                ENFT_ERROR_INVALID_GROUP_ELEMENT,
+            ),
+            (EnftError::InvalidScalar, ENFT_ERROR_INVALID_SCALAR),
+            (EnftError::Bcs(bcs::Error::Eof), ENFT_ERROR_BCS),
+            (
+                EnftError::AuthenticationFailed,
+                ENFT_ERROR_AUTHENTICATION_FAILED,
+            ),
+            (EnftError::ImageMismatch, ENFT_ERROR_IMAGE_MISMATCH),
+            (
+                EnftError::Image(image::ImageError::IoError(std::io::Error::other("image"))),
+                ENFT_ERROR_IMAGE,
+            ),
+            (EnftError::Io(std::io::Error::other("io")), ENFT_ERROR_IO),
+            (
+                EnftError::InvalidMnemonic(String::new()),
+                ENFT_ERROR_INVALID_MNEMONIC,
+            ),
+            (
+                EnftError::InvalidRegion(String::new()),
+                ENFT_ERROR_INVALID_REGION,
+            ),
+            (
+                EnftError::UnsupportedVersion(2),
+                ENFT_ERROR_UNSUPPORTED_VERSION,
+            ),
+            (EnftError::WrongKey, ENFT_ERROR_WRONG_KEY),
+            (EnftError::Keystore(String::new()), ENFT_ERROR_KEYSTORE),
+            (
+                EnftError::Unauthorized(String::new()),
+                ENFT_ERROR_UNAUTHORIZED,
+            ),
+            (EnftError::KeyServer(String::new()), ENFT_ERROR_KEY_SERVER),
+            (EnftError::Threshold(String::new()), ENFT_ERROR_THRESHOLD),
+            (EnftError::InvalidBase64, ENFT_ERROR_INVALID_BASE64),
+            (EnftError::TestVector(String::new()), ENFT_ERROR_TEST_VECTOR),
+        ];
+        for (error, code) in errors {
+            assert_eq!(error.exit_code(), code, "{}", error);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// A C program that goes through a sale with the C ABI: generate keys, list an NFT,
+// transfer the master key to the buyer and verify the proof, and encrypt and decrypt
+// an image. Built and run in CI against the release build of the library.
+
+#include <stdio.h>
+#include <string.h>
+
+#include "enft.h"
+
+#define CHECK(condition)                                                   \
+    do {                                                                   \
+        if (!(condition)) {                                                \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, \
+                    #condition);                                           \
+            return 1;                                                      \
+        }                                                                  \
+    } while (0)
+
+static int test_transfer_and_verify(void) {
+    uint8_t msk[ENFT_G1_LENGTH], recovered[ENFT_G1_LENGTH];
+    uint8_t seller_sk[ENFT_SCALAR_LENGTH], seller_pk[ENFT_G1_LENGTH];
+    uint8_t buyer_sk[ENFT_SCALAR_LENGTH], buyer_pk[ENFT_G1_LENGTH];
+    uint8_t pk[ENFT_G1_LENGTH];
+    uint8_t enc[ENFT_ENCRYPTION_LENGTH], new_enc[ENFT_ENCRYPTION_LENGTH];
+    uint8_t proof[ENFT_PROOF_LENGTH];
+
+    CHECK(enft_generate_master_key(msk) == ENFT_OK);
+    CHECK(enft_generate_encryption_key(seller_sk, seller_pk) == ENFT_OK);
+    CHECK(enft_generate_encryption_key(buyer_sk, buyer_pk) == ENFT_OK);
+    CHECK(enft_encryption_public_key(seller_sk, pk) == ENFT_OK);
+    CHECK(memcmp(pk, seller_pk, ENFT_G1_LENGTH) == 0);
+
+    // The seller lists the NFT with the master key encrypted to themselves.
+    CHECK(enft_encrypt_master_key(msk, seller_pk, enc) == ENFT_OK);
+    CHECK(enft_decrypt_master_key(enc, seller_sk, recovered) == ENFT_OK);
+    CHECK(memcmp(recovered, msk, ENFT_G1_LENGTH) == 0);
+
+    // The seller transfers the master key to the buyer.
+    CHECK(enft_transfer(msk, enc, seller_sk, buyer_pk, new_enc, proof) == ENFT_OK);
+    CHECK(enft_decrypt_master_key(new_enc, buyer_sk, recovered) == ENFT_OK);
+    CHECK(memcmp(recovered, msk, ENFT_G1_LENGTH) == 0);
+    CHECK(enft_verify_equality(seller_pk, buyer_pk, enc, new_enc, proof) == ENFT_OK);
+
+    // A proof for another receiver is rejected, and invalid keys and null pointers
+    // are errors.
+    CHECK(enft_verify_equality(seller_pk, seller_pk, enc, new_enc, proof) ==
+          ENFT_PROOF_REJECTED);
+    memset(pk, 0, ENFT_G1_LENGTH);
+    CHECK(enft_verify_equality(seller_pk, pk, enc, new_enc, proof) ==
+          ENFT_ERROR_INVALID_GROUP_ELEMENT);
+    CHECK(enft_verify_equality(seller_pk, buyer_pk, enc, new_enc, NULL) ==
+          ENFT_ERROR_NULL_POINTER);
+    CHECK(enft_generate_master_key(NULL) == ENFT_ERROR_NULL_POINTER);
+    return 0;
+}
+
+static int test_encrypt_and_decrypt_image(void) {
+    enum { WIDTH = 250, HEIGHT = 120, RGBA_LEN = WIDTH * HEIGHT * 4 };
+    static uint8_t rgba[RGBA_LEN];
+    uint8_t msk[ENFT_G1_LENGTH];
+    EnftBuffer obfuscated, ciphertext, recovered;
+
+    for (size_t i = 0; i < RGBA_LEN; i++) {
+        rgba[i] = (uint8_t)(i % 251);
+    }
+    CHECK(enft_generate_master_key(msk) == ENFT_OK);
+
+    EnftImage image = {WIDTH, HEIGHT, rgba, RGBA_LEN};
+    CHECK(enft_encrypt_image(&image, msk, "checkerboard", &obfuscated, &ciphertext) ==
+          ENFT_OK);
+    CHECK(obfuscated.len == RGBA_LEN);
+    CHECK(memcmp(obfuscated.data, rgba, RGBA_LEN) != 0);
+
+    EnftImage obfuscated_image = {WIDTH, HEIGHT, obfuscated.data, obfuscated.len};
+    CHECK(enft_decrypt_image(&obfuscated_image, ciphertext.data, ciphertext.len, msk,
+                             &recovered) == ENFT_OK);
+    CHECK(recovered.len == RGBA_LEN);
+    CHECK(memcmp(recovered.data, rgba, RGBA_LEN) == 0);
+
+    // An image of the wrong size or an unknown strategy fails without output.
+    EnftImage wrong_size = {WIDTH, HEIGHT + 1, rgba, RGBA_LEN};
+    EnftBuffer unused = {NULL, 0};
+    CHECK(enft_encrypt_image(&wrong_size, msk, NULL, &unused, &unused) ==
+          ENFT_ERROR_IMAGE_MISMATCH);
+    CHECK(enft_encrypt_image(&image, msk, "sparkles", &unused, &unused) ==
+          ENFT_ERROR_INVALID_REGION);
+    CHECK(unused.data == NULL);
+
+    enft_buffer_free(obfuscated);
+    enft_buffer_free(ciphertext);
+    enft_buffer_free(recovered);
+    enft_buffer_free(unused);
+    return 0;
+}
+
+int main(void) {
+    if (test_transfer_and_verify() != 0 || test_encrypt_and_decrypt_image() != 0) {
+        return 1;
+    }
+    printf("All tests passed\n");
+    return 0;
+}
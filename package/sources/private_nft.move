@@ -227,4 +227,222 @@ module package::private_nft {
         ts::end(scenario);
 
     }
+
+    // Equality proof test vectors of seed 72078, also written to vectors.json.
+
+    #[test]
+    fun test_vector_valid() {
+        let pk1 = bls12381::g1_from_bytes(&x"b57a4d47b7e5dff991667c1157be337291b20fd8cb3cba6af57a014aea21f50a3ac46bdf002d676d41e20ba2fa70d204");
+        let pk2 = bls12381::g1_from_bytes(&x"b1ce69ba3912e605156fdd7aec2dd5e8f64b3823b88eec1000a8f0a2f888d51151ca674c98b9845193027b6b4b37d764");
+        let enc1 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b3aef414929fae7a3cc95b8fe005d4d254d1439144f2214b6a1bbde3e21d8c8a99034aca8e7bf5f9ef078b65471326aa"),
+            ciphertext: bls12381::g1_from_bytes(&x"b6ba62db68198071a2fc314b6ba5e232fbcce65b352a8b4be018714ad1d2d482bfc5229087d084e090141bc0365cbe4e"),
+        };
+        let enc2 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b2d86bdf3412da180fdbd500503affde0eede7e2d7bcee76cfe24d97e013bebe6b26ff77cadcf848c090648ef81fd37c"),
+            ciphertext: bls12381::g1_from_bytes(&x"8b9c0a6e04eda34feafe80dfcf9de2d28bb57bd649db54c81c8c3e226434ee3a940ff6d628ca80abd97d3097df0cd730"),
+        };
+        let proof = EqualityProof {
+            s1: bls12381::scalar_from_bytes(&x"2dcb7dce2cfe7a4a716f3813058f8386fad71c5e1ba88d776d6c6ba2960c1a95"),
+            s2: bls12381::scalar_from_bytes(&x"4d975bdb85d6dbae1c875778e371da1126af8e6bd03b0e2d683df1d4c5a7d7dd"),
+            u1: bls12381::g1_from_bytes(&x"aea36595371505ba3c2ad5bdb5e5812ef86bf58eea81bdb4dfeca75233a4057903a8938408695d10e538a52d0a7d7bfb"),
+            u2: bls12381::g1_from_bytes(&x"a373c3478f629a15c0d26e9ca679315b38ba43ca724295de68070495a55a32afa0b400501ce84a8c01f1ce1e5bd2997c"),
+            v: bls12381::g1_from_bytes(&x"b73cc994b68da9dd26227ef5ea77993a0749191354877cd49fca1ea711a9e41190f8f90ac39913cc268c71053fe6e5b8"),
+        };
+        let c = fiat_shamir_challenge(&pk1, &pk2, &enc1, &enc2, &proof.u1, &proof.u2, &proof.v);
+        assert!(*group_ops::bytes(&c) == x"0014fb0e2e45849ee98dbb8a0dca97063825a531796c539feec871120f78a7ee", 0);
+        assert!(equality_verify(&pk1, &pk2, &enc1, &enc2, &proof), 1);
+    }
+
+    #[test]
+    fun test_vector_resale() {
+        let pk1 = bls12381::g1_from_bytes(&x"b1ce69ba3912e605156fdd7aec2dd5e8f64b3823b88eec1000a8f0a2f888d51151ca674c98b9845193027b6b4b37d764");
+        let pk2 = bls12381::g1_from_bytes(&x"b8bab1ea6d1034fa6144de036066babef3c48ddd1af745abece212f2a92c5f4d601c48316f70775b1c21966f1760fb17");
+        let enc1 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b2d86bdf3412da180fdbd500503affde0eede7e2d7bcee76cfe24d97e013bebe6b26ff77cadcf848c090648ef81fd37c"),
+            ciphertext: bls12381::g1_from_bytes(&x"8b9c0a6e04eda34feafe80dfcf9de2d28bb57bd649db54c81c8c3e226434ee3a940ff6d628ca80abd97d3097df0cd730"),
+        };
+        let enc2 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"a864bb534b0139629d3bb541a12f03ab58b1e9e70dc74ba68a5231e59735d1b5938b5dfb75933d372e36cbcf82601984"),
+            ciphertext: bls12381::g1_from_bytes(&x"a8d7f8500ee2f803d95a3f0eda1c11dd73ca63cfbe6498001560ef65243c41d6526b26e4f14b743a86f845e759829002"),
+        };
+        let proof = EqualityProof {
+            s1: bls12381::scalar_from_bytes(&x"16a9cb362a1e029178882c0f872522df99f54392a1f808993cfa1cd40908dc28"),
+            s2: bls12381::scalar_from_bytes(&x"6fabcd7d4ce91f9be11dbc89e381499783cef30f499ac55464b5d203753ff008"),
+            u1: bls12381::g1_from_bytes(&x"8eb6f5468c75c05b2a790dd4acb7a58c0d6a6583d7558522a1073adbf0cb381682db35a8e2937acd8cdf1efe71478acb"),
+            u2: bls12381::g1_from_bytes(&x"aeb8a4f80ea5dd561374b94f67eb1f0517f8ca5eb164816005fa34d74acff7fad9b991e919ef40dcd1f41d0e0e66d39a"),
+            v: bls12381::g1_from_bytes(&x"8c3de83a61c14df2b8a53a3699f1ab4f301d53c1a991581deb96030ace1f15ea7e0dc78fad4e8421ec0d61268676412f"),
+        };
+        let c = fiat_shamir_challenge(&pk1, &pk2, &enc1, &enc2, &proof.u1, &proof.u2, &proof.v);
+        assert!(*group_ops::bytes(&c) == x"00f9c4cf00ba12dec6759b68bac61ae8738c770a44e00276aebef30c200460c5", 0);
+        assert!(equality_verify(&pk1, &pk2, &enc1, &enc2, &proof), 1);
+    }
+
+    #[test]
+    fun test_vector_other_master_key() {
+        let pk1 = bls12381::g1_from_bytes(&x"b57a4d47b7e5dff991667c1157be337291b20fd8cb3cba6af57a014aea21f50a3ac46bdf002d676d41e20ba2fa70d204");
+        let pk2 = bls12381::g1_from_bytes(&x"b1ce69ba3912e605156fdd7aec2dd5e8f64b3823b88eec1000a8f0a2f888d51151ca674c98b9845193027b6b4b37d764");
+        let enc1 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b3aef414929fae7a3cc95b8fe005d4d254d1439144f2214b6a1bbde3e21d8c8a99034aca8e7bf5f9ef078b65471326aa"),
+            ciphertext: bls12381::g1_from_bytes(&x"b6ba62db68198071a2fc314b6ba5e232fbcce65b352a8b4be018714ad1d2d482bfc5229087d084e090141bc0365cbe4e"),
+        };
+        let enc2 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"a29e49ad6dc3c5c58413c12fd6b96b09dc70e0f1d11f9cdbf2cc927934eba2bf4aae434ea97df9a947f5012d124f2d6e"),
+            ciphertext: bls12381::g1_from_bytes(&x"863ff9c6d6af7a5edb39f306f0a0059d32b27090d7664a49822de6e7cc8e31849653149b21a936788863d4d2a7a072ab"),
+        };
+        let proof = EqualityProof {
+            s1: bls12381::scalar_from_bytes(&x"50d5469bde29e63b4a0f183fc28ed9a0bb057d4d7e46a797fa18b80760bce599"),
+            s2: bls12381::scalar_from_bytes(&x"2d306f6646a2417d6af14b32fe0500ab4c5ff4e4024b74301877591632cd0da9"),
+            u1: bls12381::g1_from_bytes(&x"8cfe118638c3fe95338fa5d2d6588f05390c246a01bb14cb3b3c1948420d489063c262418f033295b36badedb5f6d8f7"),
+            u2: bls12381::g1_from_bytes(&x"8945ad9b95683d671e7533eadb88c62249380806176ffe05db8944d85905082f180c3d4b0a9373ecdfb8c733f9aa43e3"),
+            v: bls12381::g1_from_bytes(&x"819e3c38718d62f7d1f1e60b8d676a7418361b770f233775c15f924568a3ec4d2f2a237585ae14d22faf5c6eb684c8e3"),
+        };
+        let c = fiat_shamir_challenge(&pk1, &pk2, &enc1, &enc2, &proof.u1, &proof.u2, &proof.v);
+        assert!(*group_ops::bytes(&c) == x"004de8ff04a49a63766973b86482d1482a29378c317f4e3d0cc411ee490a50ef", 0);
+        assert!(!equality_verify(&pk1, &pk2, &enc1, &enc2, &proof), 1);
+    }
+
+    #[test]
+    fun test_vector_tampered_s1() {
+        let pk1 = bls12381::g1_from_bytes(&x"b57a4d47b7e5dff991667c1157be337291b20fd8cb3cba6af57a014aea21f50a3ac46bdf002d676d41e20ba2fa70d204");
+        let pk2 = bls12381::g1_from_bytes(&x"b1ce69ba3912e605156fdd7aec2dd5e8f64b3823b88eec1000a8f0a2f888d51151ca674c98b9845193027b6b4b37d764");
+        let enc1 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b3aef414929fae7a3cc95b8fe005d4d254d1439144f2214b6a1bbde3e21d8c8a99034aca8e7bf5f9ef078b65471326aa"),
+            ciphertext: bls12381::g1_from_bytes(&x"b6ba62db68198071a2fc314b6ba5e232fbcce65b352a8b4be018714ad1d2d482bfc5229087d084e090141bc0365cbe4e"),
+        };
+        let enc2 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b2d86bdf3412da180fdbd500503affde0eede7e2d7bcee76cfe24d97e013bebe6b26ff77cadcf848c090648ef81fd37c"),
+            ciphertext: bls12381::g1_from_bytes(&x"8b9c0a6e04eda34feafe80dfcf9de2d28bb57bd649db54c81c8c3e226434ee3a940ff6d628ca80abd97d3097df0cd730"),
+        };
+        let proof = EqualityProof {
+            s1: bls12381::scalar_from_bytes(&x"2dcb7dce2cfe7a4a716f3813058f8386fad71c5e1ba88d776d6c6ba2960c1a96"),
+            s2: bls12381::scalar_from_bytes(&x"4d975bdb85d6dbae1c875778e371da1126af8e6bd03b0e2d683df1d4c5a7d7dd"),
+            u1: bls12381::g1_from_bytes(&x"aea36595371505ba3c2ad5bdb5e5812ef86bf58eea81bdb4dfeca75233a4057903a8938408695d10e538a52d0a7d7bfb"),
+            u2: bls12381::g1_from_bytes(&x"a373c3478f629a15c0d26e9ca679315b38ba43ca724295de68070495a55a32afa0b400501ce84a8c01f1ce1e5bd2997c"),
+            v: bls12381::g1_from_bytes(&x"b73cc994b68da9dd26227ef5ea77993a0749191354877cd49fca1ea711a9e41190f8f90ac39913cc268c71053fe6e5b8"),
+        };
+        let c = fiat_shamir_challenge(&pk1, &pk2, &enc1, &enc2, &proof.u1, &proof.u2, &proof.v);
+        assert!(*group_ops::bytes(&c) == x"0014fb0e2e45849ee98dbb8a0dca97063825a531796c539feec871120f78a7ee", 0);
+        assert!(!equality_verify(&pk1, &pk2, &enc1, &enc2, &proof), 1);
+    }
+
+    #[test]
+    fun test_vector_tampered_s2() {
+        let pk1 = bls12381::g1_from_bytes(&x"b57a4d47b7e5dff991667c1157be337291b20fd8cb3cba6af57a014aea21f50a3ac46bdf002d676d41e20ba2fa70d204");
+        let pk2 = bls12381::g1_from_bytes(&x"b1ce69ba3912e605156fdd7aec2dd5e8f64b3823b88eec1000a8f0a2f888d51151ca674c98b9845193027b6b4b37d764");
+        let enc1 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b3aef414929fae7a3cc95b8fe005d4d254d1439144f2214b6a1bbde3e21d8c8a99034aca8e7bf5f9ef078b65471326aa"),
+            ciphertext: bls12381::g1_from_bytes(&x"b6ba62db68198071a2fc314b6ba5e232fbcce65b352a8b4be018714ad1d2d482bfc5229087d084e090141bc0365cbe4e"),
+        };
+        let enc2 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b2d86bdf3412da180fdbd500503affde0eede7e2d7bcee76cfe24d97e013bebe6b26ff77cadcf848c090648ef81fd37c"),
+            ciphertext: bls12381::g1_from_bytes(&x"8b9c0a6e04eda34feafe80dfcf9de2d28bb57bd649db54c81c8c3e226434ee3a940ff6d628ca80abd97d3097df0cd730"),
+        };
+        let proof = EqualityProof {
+            s1: bls12381::scalar_from_bytes(&x"2dcb7dce2cfe7a4a716f3813058f8386fad71c5e1ba88d776d6c6ba2960c1a95"),
+            s2: bls12381::scalar_from_bytes(&x"4d975bdb85d6dbae1c875778e371da1126af8e6bd03b0e2d683df1d4c5a7d7de"),
+            u1: bls12381::g1_from_bytes(&x"aea36595371505ba3c2ad5bdb5e5812ef86bf58eea81bdb4dfeca75233a4057903a8938408695d10e538a52d0a7d7bfb"),
+            u2: bls12381::g1_from_bytes(&x"a373c3478f629a15c0d26e9ca679315b38ba43ca724295de68070495a55a32afa0b400501ce84a8c01f1ce1e5bd2997c"),
+            v: bls12381::g1_from_bytes(&x"b73cc994b68da9dd26227ef5ea77993a0749191354877cd49fca1ea711a9e41190f8f90ac39913cc268c71053fe6e5b8"),
+        };
+        let c = fiat_shamir_challenge(&pk1, &pk2, &enc1, &enc2, &proof.u1, &proof.u2, &proof.v);
+        assert!(*group_ops::bytes(&c) == x"0014fb0e2e45849ee98dbb8a0dca97063825a531796c539feec871120f78a7ee", 0);
+        assert!(!equality_verify(&pk1, &pk2, &enc1, &enc2, &proof), 1);
+    }
+
+    #[test]
+    fun test_vector_tampered_u1() {
+        let pk1 = bls12381::g1_from_bytes(&x"b57a4d47b7e5dff991667c1157be337291b20fd8cb3cba6af57a014aea21f50a3ac46bdf002d676d41e20ba2fa70d204");
+        let pk2 = bls12381::g1_from_bytes(&x"b1ce69ba3912e605156fdd7aec2dd5e8f64b3823b88eec1000a8f0a2f888d51151ca674c98b9845193027b6b4b37d764");
+        let enc1 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b3aef414929fae7a3cc95b8fe005d4d254d1439144f2214b6a1bbde3e21d8c8a99034aca8e7bf5f9ef078b65471326aa"),
+            ciphertext: bls12381::g1_from_bytes(&x"b6ba62db68198071a2fc314b6ba5e232fbcce65b352a8b4be018714ad1d2d482bfc5229087d084e090141bc0365cbe4e"),
+        };
+        let enc2 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b2d86bdf3412da180fdbd500503affde0eede7e2d7bcee76cfe24d97e013bebe6b26ff77cadcf848c090648ef81fd37c"),
+            ciphertext: bls12381::g1_from_bytes(&x"8b9c0a6e04eda34feafe80dfcf9de2d28bb57bd649db54c81c8c3e226434ee3a940ff6d628ca80abd97d3097df0cd730"),
+        };
+        let proof = EqualityProof {
+            s1: bls12381::scalar_from_bytes(&x"2dcb7dce2cfe7a4a716f3813058f8386fad71c5e1ba88d776d6c6ba2960c1a95"),
+            s2: bls12381::scalar_from_bytes(&x"4d975bdb85d6dbae1c875778e371da1126af8e6bd03b0e2d683df1d4c5a7d7dd"),
+            u1: bls12381::g1_from_bytes(&x"92fa18bc139c65bebad241e2fbe377ef6a6f80f9727f72d26f8f179af1c8a98abe6f2d236f2d9e9a0597c7697783c4a3"),
+            u2: bls12381::g1_from_bytes(&x"a373c3478f629a15c0d26e9ca679315b38ba43ca724295de68070495a55a32afa0b400501ce84a8c01f1ce1e5bd2997c"),
+            v: bls12381::g1_from_bytes(&x"b73cc994b68da9dd26227ef5ea77993a0749191354877cd49fca1ea711a9e41190f8f90ac39913cc268c71053fe6e5b8"),
+        };
+        let c = fiat_shamir_challenge(&pk1, &pk2, &enc1, &enc2, &proof.u1, &proof.u2, &proof.v);
+        assert!(*group_ops::bytes(&c) == x"005f55bf285df1c82614c58c18bd320ff9257ac87ba9f2c6f9896a4fade60f3c", 0);
+        assert!(!equality_verify(&pk1, &pk2, &enc1, &enc2, &proof), 1);
+    }
+
+    #[test]
+    fun test_vector_tampered_v() {
+        let pk1 = bls12381::g1_from_bytes(&x"b57a4d47b7e5dff991667c1157be337291b20fd8cb3cba6af57a014aea21f50a3ac46bdf002d676d41e20ba2fa70d204");
+        let pk2 = bls12381::g1_from_bytes(&x"b1ce69ba3912e605156fdd7aec2dd5e8f64b3823b88eec1000a8f0a2f888d51151ca674c98b9845193027b6b4b37d764");
+        let enc1 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b3aef414929fae7a3cc95b8fe005d4d254d1439144f2214b6a1bbde3e21d8c8a99034aca8e7bf5f9ef078b65471326aa"),
+            ciphertext: bls12381::g1_from_bytes(&x"b6ba62db68198071a2fc314b6ba5e232fbcce65b352a8b4be018714ad1d2d482bfc5229087d084e090141bc0365cbe4e"),
+        };
+        let enc2 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b2d86bdf3412da180fdbd500503affde0eede7e2d7bcee76cfe24d97e013bebe6b26ff77cadcf848c090648ef81fd37c"),
+            ciphertext: bls12381::g1_from_bytes(&x"8b9c0a6e04eda34feafe80dfcf9de2d28bb57bd649db54c81c8c3e226434ee3a940ff6d628ca80abd97d3097df0cd730"),
+        };
+        let proof = EqualityProof {
+            s1: bls12381::scalar_from_bytes(&x"2dcb7dce2cfe7a4a716f3813058f8386fad71c5e1ba88d776d6c6ba2960c1a95"),
+            s2: bls12381::scalar_from_bytes(&x"4d975bdb85d6dbae1c875778e371da1126af8e6bd03b0e2d683df1d4c5a7d7dd"),
+            u1: bls12381::g1_from_bytes(&x"aea36595371505ba3c2ad5bdb5e5812ef86bf58eea81bdb4dfeca75233a4057903a8938408695d10e538a52d0a7d7bfb"),
+            u2: bls12381::g1_from_bytes(&x"a373c3478f629a15c0d26e9ca679315b38ba43ca724295de68070495a55a32afa0b400501ce84a8c01f1ce1e5bd2997c"),
+            v: bls12381::g1_from_bytes(&x"99d077f3990ec750a3b5a307bc809113da689930ad4093cd95506976f16aec3aa8251a65fc0e17c6c71f8505fa499a44"),
+        };
+        let c = fiat_shamir_challenge(&pk1, &pk2, &enc1, &enc2, &proof.u1, &proof.u2, &proof.v);
+        assert!(*group_ops::bytes(&c) == x"00d01da6b20cadb51a4336291aa3df514da67f556d077c230dde48a2bf353eb1", 0);
+        assert!(!equality_verify(&pk1, &pk2, &enc1, &enc2, &proof), 1);
+    }
+
+    #[test]
+    fun test_vector_wrong_receiver() {
+        let pk1 = bls12381::g1_from_bytes(&x"b57a4d47b7e5dff991667c1157be337291b20fd8cb3cba6af57a014aea21f50a3ac46bdf002d676d41e20ba2fa70d204");
+        let pk2 = bls12381::g1_from_bytes(&x"b8bab1ea6d1034fa6144de036066babef3c48ddd1af745abece212f2a92c5f4d601c48316f70775b1c21966f1760fb17");
+        let enc1 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b3aef414929fae7a3cc95b8fe005d4d254d1439144f2214b6a1bbde3e21d8c8a99034aca8e7bf5f9ef078b65471326aa"),
+            ciphertext: bls12381::g1_from_bytes(&x"b6ba62db68198071a2fc314b6ba5e232fbcce65b352a8b4be018714ad1d2d482bfc5229087d084e090141bc0365cbe4e"),
+        };
+        let enc2 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b2d86bdf3412da180fdbd500503affde0eede7e2d7bcee76cfe24d97e013bebe6b26ff77cadcf848c090648ef81fd37c"),
+            ciphertext: bls12381::g1_from_bytes(&x"8b9c0a6e04eda34feafe80dfcf9de2d28bb57bd649db54c81c8c3e226434ee3a940ff6d628ca80abd97d3097df0cd730"),
+        };
+        let proof = EqualityProof {
+            s1: bls12381::scalar_from_bytes(&x"2dcb7dce2cfe7a4a716f3813058f8386fad71c5e1ba88d776d6c6ba2960c1a95"),
+            s2: bls12381::scalar_from_bytes(&x"4d975bdb85d6dbae1c875778e371da1126af8e6bd03b0e2d683df1d4c5a7d7dd"),
+            u1: bls12381::g1_from_bytes(&x"aea36595371505ba3c2ad5bdb5e5812ef86bf58eea81bdb4dfeca75233a4057903a8938408695d10e538a52d0a7d7bfb"),
+            u2: bls12381::g1_from_bytes(&x"a373c3478f629a15c0d26e9ca679315b38ba43ca724295de68070495a55a32afa0b400501ce84a8c01f1ce1e5bd2997c"),
+            v: bls12381::g1_from_bytes(&x"b73cc994b68da9dd26227ef5ea77993a0749191354877cd49fca1ea711a9e41190f8f90ac39913cc268c71053fe6e5b8"),
+        };
+        let c = fiat_shamir_challenge(&pk1, &pk2, &enc1, &enc2, &proof.u1, &proof.u2, &proof.v);
+        assert!(*group_ops::bytes(&c) == x"00584cdec119c5ee7a0a86464ea1247e9b4f7d4bf9861c5f6aebf4bad1aea099", 0);
+        assert!(!equality_verify(&pk1, &pk2, &enc1, &enc2, &proof), 1);
+    }
+
+    #[test]
+    fun test_vector_swapped_encryptions() {
+        let pk1 = bls12381::g1_from_bytes(&x"b57a4d47b7e5dff991667c1157be337291b20fd8cb3cba6af57a014aea21f50a3ac46bdf002d676d41e20ba2fa70d204");
+        let pk2 = bls12381::g1_from_bytes(&x"b1ce69ba3912e605156fdd7aec2dd5e8f64b3823b88eec1000a8f0a2f888d51151ca674c98b9845193027b6b4b37d764");
+        let enc1 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b2d86bdf3412da180fdbd500503affde0eede7e2d7bcee76cfe24d97e013bebe6b26ff77cadcf848c090648ef81fd37c"),
+            ciphertext: bls12381::g1_from_bytes(&x"8b9c0a6e04eda34feafe80dfcf9de2d28bb57bd649db54c81c8c3e226434ee3a940ff6d628ca80abd97d3097df0cd730"),
+        };
+        let enc2 = ElGamalEncryption {
+            ephemeral: bls12381::g1_from_bytes(&x"b3aef414929fae7a3cc95b8fe005d4d254d1439144f2214b6a1bbde3e21d8c8a99034aca8e7bf5f9ef078b65471326aa"),
+            ciphertext: bls12381::g1_from_bytes(&x"b6ba62db68198071a2fc314b6ba5e232fbcce65b352a8b4be018714ad1d2d482bfc5229087d084e090141bc0365cbe4e"),
+        };
+        let proof = EqualityProof {
+            s1: bls12381::scalar_from_bytes(&x"2dcb7dce2cfe7a4a716f3813058f8386fad71c5e1ba88d776d6c6ba2960c1a95"),
+            s2: bls12381::scalar_from_bytes(&x"4d975bdb85d6dbae1c875778e371da1126af8e6bd03b0e2d683df1d4c5a7d7dd"),
+            u1: bls12381::g1_from_bytes(&x"aea36595371505ba3c2ad5bdb5e5812ef86bf58eea81bdb4dfeca75233a4057903a8938408695d10e538a52d0a7d7bfb"),
+            u2: bls12381::g1_from_bytes(&x"a373c3478f629a15c0d26e9ca679315b38ba43ca724295de68070495a55a32afa0b400501ce84a8c01f1ce1e5bd2997c"),
+            v: bls12381::g1_from_bytes(&x"b73cc994b68da9dd26227ef5ea77993a0749191354877cd49fca1ea711a9e41190f8f90ac39913cc268c71053fe6e5b8"),
+        };
+        let c = fiat_shamir_challenge(&pk1, &pk2, &enc1, &enc2, &proof.u1, &proof.u2, &proof.v);
+        assert!(*group_ops::bytes(&c) == x"008966d03c4cda47131d74b3eaa35684c07faaee28c231c74f997c6ddab802df", 0);
+        assert!(!equality_verify(&pk1, &pk2, &enc1, &enc2, &proof), 1);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,302 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! WebAssembly bindings of the encrypted NFT library, so that the frontend uses the
+//! same obfuscation and crypto as the CLI. Keys, encrypted master keys and proofs are
+//! passed as their bytes in the CLI's encoding: compressed G1 elements, big-endian
+//! scalars and BCS. Images are RGBA buffers, e.g. from a canvas.
+
+use enft_cli::elgamal;
+use enft_cli::elgamal::{ElGamalEncryption, EqualityProof};
+use enft_cli::region::ChunkGeometry;
+use enft_cli::sampling::BuiltinStrategy;
+use enft_cli::style::ObfuscationStyle;
+use enft_cli::utils;
+use enft_cli::utils::{g1_element_from_bytes, scalar_from_bytes, LoadedImage};
+use enft_cli::FullCipherText;
+use fastcrypto::groups::bls12381::{G1Element, Scalar};
+use fastcrypto::groups::{GroupElement, Scalar as _};
+use fastcrypto::serde_helpers::ToFromByteArray;
+use wasm_bindgen::prelude::*;
+
+/// Generate a master key.
+#[wasm_bindgen(js_name = generateMasterKey)]
+pub fn generate_master_key() -> Vec<u8> {
+    let msk = G1Element::generator() * Scalar::rand(&mut rand::thread_rng());
+    msk.to_byte_array().to_vec()
+}
+
+/// Generate an encryption private key.
+#[wasm_bindgen(js_name = generateEncryptionKey)]
+pub fn generate_encryption_key() -> Vec<u8> {
+    Scalar::rand(&mut rand::thread_rng())
+        .to_byte_array()
+        .to_vec()
+}
+
+/// The encryption pubkey of a private key.
+#[wasm_bindgen(js_name = encryptionPublicKey)]
+pub fn encryption_public_key(sk: &[u8]) -> Result<Vec<u8>, JsError> {
+    let pk = G1Element::generator() * scalar_from_bytes(sk.to_vec())?;
+    Ok(pk.to_byte_array().to_vec())
+}
+
+/// Encrypt the master key under the given pubkey, giving the encrypted master key
+/// that is posted on-chain when listing.
+#[wasm_bindgen(js_name = encryptMasterKey)]
+pub fn encrypt_master_key(master_key: &[u8], pk: &[u8]) -> Result<Vec<u8>, JsError> {
+    let (enc, _) = elgamal::encrypt(
+        &g1_element_from_bytes(master_key.to_vec())?,
+        &g1_element_from_bytes(pk.to_vec())?,
+        &mut rand::thread_rng(),
+    );
+    Ok(bcs::to_bytes(&enc)?)
+}
+
+/// Decrypt the master key with the private key of the pubkey it was encrypted to.
+#[wasm_bindgen(js_name = decryptMasterKey)]
+pub fn decrypt_master_key(enc_msk: &[u8], sk: &[u8]) -> Result<Vec<u8>, JsError> {
+    let enc: ElGamalEncryption = bcs::from_bytes(enc_msk)?;
+    let msk = elgamal::decrypt(&enc, &scalar_from_bytes(sk.to_vec())?);
+    Ok(msk.to_byte_array().to_vec())
+}
+
+/// The new encrypted master key and the equality proof of a transfer.
+#[wasm_bindgen]
+pub struct Transfer {
+    encrypted_master_key: Vec<u8>,
+    proof: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl Transfer {
+    #[wasm_bindgen(getter, js_name = encryptedMasterKey)]
+    pub fn encrypted_master_key(&self) -> Vec<u8> {
+        self.encrypted_master_key.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn proof(&self) -> Vec<u8> {
+        self.proof.clone()
+    }
+}
+
+/// Re-encrypt the master key under the receiver's pubkey and prove that it is the key
+/// of the previous encryption under the sender's pubkey.
+#[wasm_bindgen]
+pub fn transfer(
+    master_key: &[u8],
+    prev_enc_msk: &[u8],
+    sender_sk: &[u8],
+    receiver_pk: &[u8],
+) -> Result<Transfer, JsError> {
+    let (enc, proof) = elgamal::transfer(
+        &g1_element_from_bytes(master_key.to_vec())?,
+        &bcs::from_bytes(prev_enc_msk)?,
+        &scalar_from_bytes(sender_sk.to_vec())?,
+        &g1_element_from_bytes(receiver_pk.to_vec())?,
+        &mut rand::thread_rng(),
+    );
+    Ok(Transfer {
+        encrypted_master_key: bcs::to_bytes(&enc)?,
+        proof: bcs::to_bytes(&proof)?,
+    })
+}
+
+/// Verify the equality proof of a transfer. Returns false if the proof is rejected
+/// and throws if an argument cannot be decoded.
+#[wasm_bindgen(js_name = verifyEquality)]
+pub fn verify_equality(
+    sender_pk: &[u8],
+    receiver_pk: &[u8],
+    prev_enc_msk: &[u8],
+    new_enc_msk: &[u8],
+    proof: &[u8],
+) -> Result<bool, JsError> {
+    let proof: EqualityProof = bcs::from_bytes(proof)?;
+    Ok(elgamal::verify_equality(
+        &g1_element_from_bytes(sender_pk.to_vec())?,
+        &g1_element_from_bytes(receiver_pk.to_vec())?,
+        &bcs::from_bytes(prev_enc_msk)?,
+        &bcs::from_bytes(new_enc_msk)?,
+        &proof,
+    )
+    .is_ok())
+}
+
+/// How an image is obfuscated, with the same defaults as `enft-cli encrypt`.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct ObfuscationOptions {
+    strategy: BuiltinStrategy,
+    geometry: ChunkGeometry,
+    style: ObfuscationStyle,
+}
+
+#[wasm_bindgen]
+impl ObfuscationOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The sampling strategy, e.g. `percent:20` or `seeded:<seed>:<n>`.
+    #[wasm_bindgen(js_name = setStrategy)]
+    pub fn set_strategy(&mut self, strategy: &str) -> Result<(), JsError> {
+        self.strategy = strategy.parse().map_err(|e: String| JsError::new(&e))?;
+        Ok(())
+    }
+
+    /// The size in pixels of the chunks that the image is divided into.
+    #[wasm_bindgen(js_name = setChunkSize)]
+    pub fn set_chunk_size(&mut self, width: u32, height: u32) -> Result<(), JsError> {
+        if width == 0 || height == 0 {
+            return Err(JsError::new("The chunk size must be positive"));
+        }
+        self.geometry = ChunkGeometry { width, height };
+        Ok(())
+    }
+
+    /// How the hidden pixels look, e.g. `transparent` or `pixelate:<block>`.
+    #[wasm_bindgen(js_name = setStyle)]
+    pub fn set_style(&mut self, style: &str) -> Result<(), JsError> {
+        self.style = style.parse().map_err(|e: String| JsError::new(&e))?;
+        Ok(())
+    }
+}
+
+/// The public preview of an image and the ciphertext of its hidden pixels.
+#[wasm_bindgen]
+pub struct ObfuscatedImage {
+    rgba: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl ObfuscatedImage {
+    /// The RGBA pixels of the obfuscated image.
+    #[wasm_bindgen(getter)]
+    pub fn rgba(&self) -> Vec<u8> {
+        self.rgba.clone()
+    }
+
+    /// The ciphertext, as written by `enft-cli --encoding raw encrypt`.
+    #[wasm_bindgen(getter)]
+    pub fn ciphertext(&self) -> Vec<u8> {
+        self.ciphertext.clone()
+    }
+}
+
+/// Sample chunks of the image, hide them in the preview and encrypt them under the
+/// master key, like `load_and_sample_image` and `encrypt` in the CLI.
+#[wasm_bindgen(js_name = obfuscateImage)]
+pub fn obfuscate_image(
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+    master_key: &[u8],
+    options: &ObfuscationOptions,
+) -> Result<ObfuscatedImage, JsError> {
+    let msk = g1_element_from_bytes(master_key.to_vec())?;
+    let image = LoadedImage::from_rgba(width, height, rgba)?;
+    let preprocessed =
+        utils::sample_image(&image, &options.strategy, options.geometry, &options.style)?;
+    let ciphertext = FullCipherText::encrypt(&preprocessed, &msk, &mut rand::thread_rng())?;
+    Ok(ObfuscatedImage {
+        rgba: preprocessed.obfuscated_image[8..].to_vec(),
+        ciphertext: ciphertext.to_bytes(),
+    })
+}
+
+/// Recover the RGBA pixels of the original image from the obfuscated image, the
+/// ciphertext and the master key.
+#[wasm_bindgen(js_name = recoverImage)]
+pub fn recover_image(
+    width: u32,
+    height: u32,
+    obfuscated_rgba: &[u8],
+    ciphertext: &[u8],
+    master_key: &[u8],
+) -> Result<Vec<u8>, JsError> {
+    let image = LoadedImage::from_rgba(width, height, obfuscated_rgba)?;
+    let recovered = utils::recover_image(
+        &image.data,
+        FullCipherText::from_bytes(ciphertext)?,
+        g1_element_from_bytes(master_key.to_vec())?,
+    )?;
+    Ok(recovered[8..].to_vec())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn test_transfer_and_verify() {
+        let msk = generate_master_key();
+        let seller_sk = generate_encryption_key();
+        let buyer_sk = generate_encryption_key();
+        let seller_pk = encryption_public_key(&seller_sk).unwrap();
+        let buyer_pk = encryption_public_key(&buyer_sk).unwrap();
+
+        let enc = encrypt_master_key(&msk, &seller_pk).unwrap();
+        assert_eq!(decrypt_master_key(&enc, &seller_sk).unwrap(), msk);
+
+        let result = transfer(&msk, &enc, &seller_sk, &buyer_pk).unwrap();
+        let new_enc = result.encrypted_master_key();
+        assert_eq!(decrypt_master_key(&new_enc, &buyer_sk).unwrap(), msk);
+        assert!(verify_equality(&seller_pk, &buyer_pk, &enc, &new_enc, &result.proof()).unwrap());
+
+        // A proof for another receiver is rejected, and malformed input throws.
+        assert!(!verify_equality(&seller_pk, &seller_pk, &enc, &new_enc, &result.proof()).unwrap());
+        assert!(verify_equality(&seller_pk, &buyer_pk, &enc, &new_enc, &[1, 2, 3]).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_obfuscate_and_recover() {
+        let (width, height) = (250, 120);
+        let rgba: Vec<u8> = (0..width * height * 4).map(|i| (i % 251) as u8).collect();
+        let msk = generate_master_key();
+        let mut options = ObfuscationOptions::new();
+        options.set_strategy("checkerboard").unwrap();
+        options.set_chunk_size(50, 40).unwrap();
+        assert!(options.set_style("sparkles").is_err());
+
+        let obfuscated = obfuscate_image(width, height, &rgba, &msk, &options).unwrap();
+        assert_ne!(obfuscated.rgba(), rgba);
+        let recovered = recover_image(
+            width,
+            height,
+            &obfuscated.rgba(),
+            &obfuscated.ciphertext(),
+            &msk,
+        )
+        .unwrap();
+        assert_eq!(recovered, rgba);
+
+        // Another master key or a buffer of the wrong size fails.
+        let other = generate_master_key();
+        assert!(recover_image(
+            width,
+            height,
+            &obfuscated.rgba(),
+            &obfuscated.ciphertext(),
+            &other
+        )
+        .is_err());
+        assert!(obfuscate_image(width, height + 1, &rgba, &msk, &options).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_oversized_dimensions() {
+        // 32768 * 32768 * 4 bytes does not fit in a 32-bit usize, which must be an
+        // error rather than a wrapped size or a trap.
+        let msk = generate_master_key();
+        let options = ObfuscationOptions::new();
+        for (width, height) in [(32768, 32768), (u32::MAX, u32::MAX)] {
+            assert!(obfuscate_image(width, height, &[], &msk, &options).is_err());
+            assert!(recover_image(width, height, &[], &[], &msk).is_err());
+        }
+    }
+}